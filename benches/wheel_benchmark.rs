@@ -22,7 +22,7 @@ fn benchmark_insert(c: &mut Criterion) {
     let mut random_deadlines = Vec::with_capacity(n);
     for _ in 0..n {
         // Random deadlines between 1 and 1000000
-        random_deadlines.push(rng.gen_range(1..1000_000));
+        random_deadlines.push(rng.gen_range(1..1_000_000));
     }
     let mut group = c.benchmark_group("Insertion");
     group.sample_size(10); // Reduce samples because 1M takes time
@@ -31,16 +31,24 @@ fn benchmark_insert(c: &mut Criterion) {
         b.iter(|| {
             let mut wheel = TimingWheel::new();
             // using the pre-calculated random deadlines
-            for (i,&deadline) in random_deadlines.iter().enumerate() {
-                wheel.insert(black_box(i), black_box(deadline));
+            for (i,&delay) in random_deadlines.iter().enumerate() {
+                wheel.insert_in(black_box(i), black_box(delay));
             }
         })
     });
 
+    group.bench_function("Wheel Insert Batch 1M", |b| {
+        b.iter(|| {
+            let mut wheel = TimingWheel::new();
+            // One slab reservation up front instead of growing with each push
+            black_box(wheel.insert_batch(random_deadlines.iter().copied().enumerate()));
+        })
+    });
+
     group.bench_function("Heap Insert 1M", |b| {
         b.iter(|| {
             let mut heap = BinaryHeap::new();
-            for (i,&deadline) in random_deadlines.iter().enumerate()  {
+            for &deadline in random_deadlines.iter() {
                 heap.push(Reverse(black_box(deadline)));
             }
         })
@@ -72,6 +80,19 @@ fn benchmark_cancel(c: &mut Criterion) {
         )
     });
 
+    group.bench_function("Wheel Cancel Batch", |b| {
+        b.iter_with_setup(
+            || {
+                let mut wheel = TimingWheel::new();
+                let ids: Vec<_> = (0..n).map(|i| wheel.insert(i, i as u64)).collect();
+                (wheel, ids, Vec::with_capacity(n))
+            },
+            |(mut wheel, ids, mut out)| {
+                black_box(wheel.cancel_batch(&ids, &mut out));
+            },
+        )
+    });
+
     group.bench_function("Heap Cancel", |b| {
         b.iter_with_setup(
             || {
@@ -93,5 +114,44 @@ fn benchmark_cancel(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, benchmark_insert, benchmark_cancel);
+fn benchmark_reschedule(c: &mut Criterion) {
+    let n = 10_000;
+
+    let mut group = c.benchmark_group("Reschedule");
+
+    group.bench_function("Wheel Reschedule", |b| {
+        b.iter_with_setup(
+            || {
+                let mut wheel = TimingWheel::new();
+                let ids: Vec<_> = (0..n).map(|i| wheel.insert(i, i as u64)).collect();
+                (wheel, ids)
+            },
+            |(mut wheel, ids)| {
+                // Push every timeout out, keeping the same handles
+                for (i, id) in ids.into_iter().enumerate() {
+                    wheel.reschedule(id, black_box(i as u64 + 5_000));
+                }
+            },
+        )
+    });
+
+    group.bench_function("Wheel Cancel + Insert", |b| {
+        b.iter_with_setup(
+            || {
+                let mut wheel = TimingWheel::new();
+                let ids: Vec<_> = (0..n).map(|i| wheel.insert(i, i as u64)).collect();
+                (wheel, ids)
+            },
+            |(mut wheel, ids)| {
+                for (i, id) in ids.into_iter().enumerate() {
+                    let task = wheel.cancel(id).unwrap();
+                    wheel.insert(task, black_box(i as u64 + 5_000));
+                }
+            },
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_insert, benchmark_cancel, benchmark_reschedule);
 criterion_main!(benches);
@@ -0,0 +1,91 @@
+//! Idle-timeout manager for a set of connections.
+//!
+//! Every connection holds one timer, armed `IDLE_TIMEOUT` ticks out. Activity pushes it
+//! back with `reschedule`, closing a connection cancels it, and whatever `tick` hands
+//! back has been idle for too long and is reaped.
+//!
+//! Time is driven by a scripted loop rather than a wall clock, so a run is reproducible:
+//! `cargo run --example idle_timeout`.
+
+use sharded_timing_wheel::{Key, TimingWheel};
+
+const IDLE_TIMEOUT: u64 = 30;
+const CONNECTIONS: usize = 5;
+
+/// Something a connection did at a given tick
+#[derive(Debug, Clone, Copy)]
+enum Event {
+    Activity(usize),
+    Close(usize),
+}
+
+/// The scripted traffic: `(tick, event)`, in tick order
+fn script() -> Vec<(u64, Event)> {
+    let mut events = Vec::new();
+    // Connection 0 is chatty until tick 100
+    events.extend((10..=100).step_by(10).map(|t| (t, Event::Activity(0))));
+    // Connection 1 never says anything after connecting
+    // Connection 2 is active twice, then goes quiet
+    events.extend([(25, Event::Activity(2)), (50, Event::Activity(2))]);
+    // Connection 3 closes cleanly before it could time out
+    events.push((20, Event::Close(3)));
+    // Connection 4 is active once, and again after it was already reaped
+    events.extend([(29, Event::Activity(4)), (70, Event::Activity(4))]);
+    events.sort_by_key(|&(tick, _)| tick);
+    events
+}
+
+/// Runs the script until `until`, returning `(tick, connection)` for every timeout
+fn run(until: u64) -> Vec<(u64, usize)> {
+    let mut wheel = TimingWheel::new();
+    // Every connection connects at tick 0; `None` once it is gone
+    let mut timers: Vec<Option<Key>> = (0..CONNECTIONS)
+        .map(|conn| Some(wheel.insert_in(conn, IDLE_TIMEOUT)))
+        .collect();
+
+    let mut events = script().into_iter().peekable();
+    let mut timed_out = Vec::new();
+    let mut expired = Vec::new();
+    while wheel.current_time() < until {
+        let now = wheel.current_time();
+
+        // Apply this tick's traffic before reaping, so activity on the deadline tick
+        // still keeps the connection alive
+        while let Some((_, event)) = events.next_if(|&(tick, _)| tick == now) {
+            match event {
+                Event::Activity(conn) => {
+                    if let Some(key) = timers[conn] {
+                        // Rescheduling moves the timer, handing back the key for its new slot
+                        timers[conn] = wheel.reschedule(key, now + IDLE_TIMEOUT);
+                    }
+                }
+                Event::Close(conn) => {
+                    if let Some(key) = timers[conn].take() {
+                        wheel.cancel(key);
+                    }
+                }
+            }
+        }
+
+        wheel.tick(&mut expired);
+        for conn in expired.drain(..) {
+            timers[conn] = None;
+            timed_out.push((now, conn));
+        }
+    }
+    timed_out
+}
+
+fn main() {
+    for (tick, conn) in run(200) {
+        println!("tick {tick:>3}: connection {conn} timed out after {IDLE_TIMEOUT} idle ticks");
+    }
+}
+
+#[test]
+fn reaps_exactly_the_idle_connections() {
+    // 3 closed cleanly, and 4's late activity came after it was reaped
+    assert_eq!(run(200), vec![(30, 1), (59, 4), (80, 2), (130, 0)]);
+    // Nothing times out before its first deadline
+    assert!(run(30).is_empty());
+}
@@ -0,0 +1,149 @@
+use crate::config::WheelConfig;
+use crate::slab::SlabStrategy;
+use crate::wheel::{FirePolicy, TimingWheel};
+use core::fmt;
+use core::marker::PhantomData;
+use core::time::Duration;
+
+/// When an idle-heavy wheel should give slab memory back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ShrinkPolicy {
+    /// Shrink once fewer than this percentage of slab slots are occupied
+    pub(crate) target_utilization: u8,
+    /// Ticks between checks; at most one shrink happens per interval
+    pub(crate) check_interval: u64,
+}
+
+/// Configures a `TimingWheel` before it is created.
+///
+/// ```
+/// use std::time::Duration;
+/// use sharded_timing_wheel::TimingWheel;
+///
+/// let wheel: TimingWheel<u32> = TimingWheel::builder()
+///     .resolution(Duration::from_millis(10))
+///     .auto_shrink(25, 1_000)
+///     .build();
+/// assert_eq!(wheel.resolution(), Duration::from_millis(10));
+/// ```
+pub struct WheelBuilder<T> {
+    pub(crate) resolution: Duration,
+    pub(crate) config: WheelConfig,
+    pub(crate) capacity: usize,
+    pub(crate) shrink: Option<ShrinkPolicy>,
+    pub(crate) fire_policy: FirePolicy,
+    pub(crate) max_timers: Option<usize>,
+    pub(crate) slab_strategy: SlabStrategy,
+    // The task type is fixed up front so `TimingWheel::builder()` infers it from `build()`
+    _task: PhantomData<fn() -> T>,
+}
+
+impl<T> fmt::Debug for WheelBuilder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WheelBuilder")
+            .field("resolution", &self.resolution)
+            .field("config", &self.config)
+            .field("capacity", &self.capacity)
+            .field("shrink", &self.shrink)
+            .field("fire_policy", &self.fire_policy)
+            .field("max_timers", &self.max_timers)
+            .field("slab_strategy", &self.slab_strategy)
+            .finish()
+    }
+}
+
+impl<T> Clone for WheelBuilder<T> {
+    fn clone(&self) -> Self {
+        Self {
+            resolution: self.resolution,
+            config: self.config,
+            capacity: self.capacity,
+            shrink: self.shrink,
+            fire_policy: self.fire_policy,
+            max_timers: self.max_timers,
+            slab_strategy: self.slab_strategy,
+            _task: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for WheelBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> WheelBuilder<T> {
+    pub fn new() -> Self {
+        Self {
+            resolution: Duration::from_millis(1),
+            config: WheelConfig::default(),
+            capacity: 1024,
+            shrink: None,
+            fire_policy: FirePolicy::default(),
+            max_timers: None,
+            slab_strategy: SlabStrategy::default(),
+            _task: PhantomData,
+        }
+    }
+
+    /// Wall-clock length of one tick. Must be non-zero.
+    pub fn resolution(mut self, tick: Duration) -> Self {
+        self.resolution = tick;
+        self
+    }
+
+    /// Level layout of the wheel; see `WheelConfig`
+    pub fn config(mut self, config: WheelConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Number of timers the wheel holds before its storage has to grow
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Releases slab memory automatically after a peak. Every `check_interval` ticks, if
+    /// fewer than `target_utilization` percent of the slots are in use, the free tail of
+    /// the slab is dropped, leaving enough headroom to sit back at the target. The gap
+    /// between the threshold and a full slab keeps it from growing and shrinking in turn.
+    pub fn auto_shrink(mut self, target_utilization: u8, check_interval: u64) -> Self {
+        assert!(
+            (1..=100).contains(&target_utilization),
+            "target utilization must be a percentage in 1..=100"
+        );
+        assert!(
+            check_interval > 0,
+            "check interval must be at least one tick"
+        );
+        self.shrink = Some(ShrinkPolicy {
+            target_utilization,
+            check_interval,
+        });
+        self
+    }
+
+    /// What inserts do with a deadline that is already in the past; see `FirePolicy`
+    pub fn fire_policy(mut self, policy: FirePolicy) -> Self {
+        self.fire_policy = policy;
+        self
+    }
+
+    /// Caps the number of pending timers; see `TimingWheel::with_max_timers`
+    pub fn max_timers(mut self, max: usize) -> Self {
+        self.max_timers = Some(max);
+        self
+    }
+
+    /// Which free slot the timer storage reuses first; see `SlabStrategy`
+    pub fn slab_strategy(mut self, strategy: SlabStrategy) -> Self {
+        self.slab_strategy = strategy;
+        self
+    }
+
+    pub fn build(&self) -> TimingWheel<T> {
+        TimingWheel::from_builder(self)
+    }
+}
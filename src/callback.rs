@@ -0,0 +1,113 @@
+use crate::slab::Key;
+use crate::wheel::TimingWheel;
+use alloc::boxed::Box;
+
+/// A boxed closure run when its timer fires
+pub type Callback = Box<dyn FnOnce() + Send>;
+
+/// A timing wheel whose timers are closures, run on the tick they expire.
+///
+/// `TimingWheel<Box<dyn FnOnce() + Send>>` works just as well; this only saves the
+/// boxing at every insert and the loop calling each expired closure.
+#[derive(Default)]
+pub struct CallbackWheel {
+    wheel: TimingWheel<Callback>,
+}
+
+impl CallbackWheel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Schedules `callback` to run on the absolute tick `deadline`
+    pub fn insert<F: FnOnce() + Send + 'static>(&mut self, callback: F, deadline: u64) -> Key {
+        self.wheel.insert(Box::new(callback), deadline)
+    }
+
+    /// Schedules `callback` to run `ticks_from_now` ticks after `current_time()`
+    pub fn insert_in<F: FnOnce() + Send + 'static>(
+        &mut self,
+        callback: F,
+        ticks_from_now: u64,
+    ) -> Key {
+        self.wheel.insert_in(Box::new(callback), ticks_from_now)
+    }
+
+    /// Cancels a pending callback, handing it back unrun
+    pub fn cancel(&mut self, key: Key) -> Option<Callback> {
+        self.wheel.cancel(key)
+    }
+
+    /// Advances one tick and runs every callback that expired on it, in expiry order.
+    /// Returns how many ran.
+    ///
+    /// The callbacks run while the wheel is borrowed, so they cannot schedule new timers
+    /// on it directly; send what they want scheduled back through a channel or queue.
+    /// If one panics, the tick is still completed and the others due on it are dropped.
+    pub fn tick_and_run(&mut self) -> usize {
+        let mut ran = 0;
+        self.wheel.tick_with(|callback| {
+            callback();
+            ran += 1;
+        });
+        ran
+    }
+
+    pub fn current_time(&self) -> u64 {
+        self.wheel.current_time()
+    }
+
+    pub fn len(&self) -> usize {
+        self.wheel.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.wheel.is_empty()
+    }
+
+    /// The wheel underneath, for everything not mirrored here
+    pub fn wheel_mut(&mut self) -> &mut TimingWheel<Callback> {
+        &mut self.wheel
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_expired_callbacks_run_on_their_tick() {
+        let mut wheel = CallbackWheel::new();
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        for deadline in [2, 2, 70, 5_000] {
+            let counter = Arc::clone(&counter);
+            wheel.insert(
+                move || {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                },
+                deadline,
+            );
+        }
+        let cancelled = {
+            let counter = Arc::clone(&counter);
+            wheel.insert_in(move || panic!("ran {counter:?} after cancel"), 3)
+        };
+        assert!(wheel.cancel(cancelled).is_some());
+
+        let mut ran_by_tick = Vec::new();
+        while !wheel.is_empty() {
+            let tick = wheel.current_time();
+            let ran = wheel.tick_and_run();
+            if ran > 0 {
+                ran_by_tick.push((tick, ran));
+            }
+        }
+        assert_eq!(ran_by_tick, vec![(2, 2), (70, 1), (5_000, 1)]);
+        assert_eq!(counter.load(Ordering::SeqCst), 4);
+        // Only the test's own handle is left; every callback was run or dropped
+        assert_eq!(Arc::strong_count(&counter), 1);
+    }
+}
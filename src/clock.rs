@@ -0,0 +1,62 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+/// Source of wall-clock time for the blocking helpers on `TimingWheel`
+pub trait Clock {
+    fn now(&self) -> Instant;
+
+    /// Blocks until `now() >= deadline`
+    fn sleep_until(&self, deadline: Instant);
+}
+
+/// The real monotonic clock, sleeping the current thread
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep_until(&self, deadline: Instant) {
+        let now = Instant::now();
+        if deadline > now {
+            std::thread::sleep(deadline - now);
+        }
+    }
+}
+
+/// A manually driven clock for deterministic tests.
+/// Sleeping jumps the clock straight to the requested instant.
+#[derive(Debug)]
+pub struct MockClock {
+    now: Cell<Instant>,
+}
+
+impl MockClock {
+    pub fn new(start: Instant) -> Self {
+        Self {
+            now: Cell::new(start),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+
+    pub fn set(&self, now: Instant) {
+        self.now.set(now);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+
+    fn sleep_until(&self, deadline: Instant) {
+        if deadline > self.now.get() {
+            self.now.set(deadline);
+        }
+    }
+}
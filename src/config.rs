@@ -0,0 +1,172 @@
+/// Shape of the wheel hierarchy: `num_levels` wheels of `2^wheel_bits` slots each.
+///
+/// Timers up to `2^(wheel_bits * num_levels)` ticks out live in the wheels; anything further
+/// waits in the overflow set. More bits per level means fewer cascades but more slots to
+/// scan, more levels means a longer horizon. The default is 4 levels of 64 slots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WheelConfig {
+    pub wheel_bits: u32,
+    pub num_levels: u32,
+}
+
+impl Default for WheelConfig {
+    fn default() -> Self {
+        Self {
+            wheel_bits: 6,
+            num_levels: 4,
+        }
+    }
+}
+
+impl WheelConfig {
+    /// Slots are recorded in a byte per entry
+    pub const MAX_WHEEL_BITS: u32 = 8;
+
+    pub fn new(wheel_bits: u32, num_levels: u32) -> Self {
+        Self {
+            wheel_bits,
+            num_levels,
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if a level has fewer than 1 or more than `MAX_WHEEL_BITS` bits, if there are
+    /// no levels, or if the horizon does not fit in a tick counter.
+    pub(crate) fn validate(&self) {
+        assert!(
+            (1..=Self::MAX_WHEEL_BITS).contains(&self.wheel_bits),
+            "wheel_bits must be in 1..={}",
+            Self::MAX_WHEEL_BITS
+        );
+        assert!(self.num_levels >= 1, "a wheel needs at least one level");
+        assert!(
+            self.wheel_bits * self.num_levels < u64::BITS,
+            "horizon of 2^{} ticks does not fit in u64",
+            self.wheel_bits * self.num_levels
+        );
+    }
+
+    /// Slots per level
+    pub fn wheel_size(&self) -> usize {
+        1 << self.wheel_bits
+    }
+
+    /// Distance in ticks from which timers go to the overflow set
+    pub fn horizon(&self) -> u64 {
+        1 << (self.wheel_bits * self.num_levels)
+    }
+
+    pub(crate) fn mask(&self) -> u64 {
+        (1 << self.wheel_bits) - 1
+    }
+
+    /// Bit offset of `level`'s slot index within a tick
+    pub(crate) fn shift(&self, level: usize) -> u32 {
+        level as u32 * self.wheel_bits
+    }
+
+    /// `TimerEntry::level` marker for entries parked in the overflow set
+    pub(crate) fn overflow_level(&self) -> u8 {
+        self.num_levels as u8
+    }
+
+    /// Where a timer due at `deadline` belongs when the wheel is at `current`, as
+    /// `(level, slot)`. The single source of truth for insert, reschedule and cascade.
+    ///
+    /// The level comes from the distance to the deadline and the slot from the deadline's
+    /// own bits at that level, which is the bucket the cascade schedule visits before it is
+    /// due. A deadline already in the past goes into the current level-0 slot so the very
+    /// next tick fires it; one a full horizon or more away maps to `(overflow_level, 0)`.
+    pub(crate) fn placement(&self, current: u64, deadline: u64) -> (u8, u8) {
+        if deadline <= current {
+            return (0, (current & self.mask()) as u8);
+        }
+
+        let duration = deadline - current;
+        if duration >= self.horizon() {
+            return (self.overflow_level(), 0);
+        }
+
+        // Each level covers `wheel_bits` more bits of distance than the one below it
+        let level = (u64::BITS - 1 - duration.leading_zeros()) / self.wheel_bits;
+        let slot = (deadline >> (level * self.wheel_bits)) & self.mask();
+        (level as u8, slot as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HORIZON: u64 = 1 << 24;
+
+    #[test]
+    fn test_placement_levels_and_slots() {
+        let c = WheelConfig::default();
+        let overflow = c.overflow_level();
+
+        // Distance picks the level, the deadline's own bits pick the slot
+        assert_eq!(c.placement(0, 1), (0, 1));
+        assert_eq!(c.placement(0, 63), (0, 63));
+        assert_eq!(c.placement(0, 64), (1, 1));
+        assert_eq!(c.placement(0, 4_095), (1, 63));
+        assert_eq!(c.placement(0, 4_096), (2, 1));
+        assert_eq!(c.placement(0, (1 << 18) - 1), (2, 63));
+        assert_eq!(c.placement(0, 1 << 18), (3, 1));
+        assert_eq!(c.placement(0, HORIZON - 1), (3, 63));
+        assert_eq!(c.placement(0, HORIZON), (overflow, 0));
+        assert_eq!(c.placement(10, HORIZON + 9), (3, 0));
+
+        // Mid-rotation: slot bits come from the absolute deadline, not the distance
+        assert_eq!(c.placement(70, 100), (0, 100 & 63));
+        assert_eq!(c.placement(70, 4_160), (1, 1));
+
+        // Due now or overdue: the current level-0 slot, which the next tick processes
+        assert_eq!(c.placement(500, 500), (0, (500 & 63) as u8));
+        assert_eq!(c.placement(500, 3), (0, (500 & 63) as u8));
+    }
+
+    #[test]
+    fn test_placement_is_stable_under_rebase_and_near_wrap() {
+        let c = WheelConfig::default();
+        let cases = [(0, 1), (5, 69), (63, 64), (100, 5_000), (4_000, 300_000)];
+        // Rebasing both clocks by whole horizons must not change where a timer goes
+        for base in [HORIZON, 7 * HORIZON, u64::MAX - HORIZON * 2 + 1] {
+            let base = base - base % HORIZON;
+            for (current, deadline) in cases {
+                assert_eq!(
+                    c.placement(base + current, base + deadline),
+                    c.placement(current, deadline),
+                    "rebased by {base}: {current} -> {deadline}"
+                );
+            }
+        }
+
+        // Right at the end of the tick space nothing overflows arithmetic
+        assert_eq!(c.placement(u64::MAX - 1, u64::MAX), (0, 63));
+        assert_eq!(
+            c.placement(u64::MAX - 10, 5),
+            (0, ((u64::MAX - 10) & 63) as u8)
+        );
+        assert_eq!(c.placement(0, u64::MAX), (c.overflow_level(), 0));
+    }
+
+    #[test]
+    fn test_placement_follows_config() {
+        // Two levels of 16 slots: horizon of 256 ticks
+        let c = WheelConfig::new(4, 2);
+        c.validate();
+        assert_eq!((c.wheel_size(), c.horizon()), (16, 256));
+        assert_eq!(c.placement(0, 15), (0, 15));
+        assert_eq!(c.placement(0, 16), (1, 1));
+        assert_eq!(c.placement(0, 255), (1, 15));
+        assert_eq!(c.placement(0, 256), (2, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "wheel_bits")]
+    fn test_validate_rejects_wide_levels() {
+        WheelConfig::new(9, 2).validate();
+    }
+}
@@ -0,0 +1,258 @@
+use crate::error::ClockWentBackwards;
+use crate::slab::Key;
+use crate::wheel::TimingWheel;
+use alloc::collections::VecDeque;
+
+/// A queue of values that become available once their delay has passed, in the style
+/// of tokio-util's `DelayQueue` but driven by an explicit clock instead of a runtime.
+///
+/// Time only moves through `advance` and `set_time`; expired values then come out of
+/// `poll_expired`. As with `TimingWheel`, a value due at tick `d` is returned once
+/// `current_time()` is past `d`.
+///
+/// A key stays valid from `insert` until its value is handed back, by `remove` or by
+/// `poll_expired`. That includes the stretch where the value has expired but was not
+/// polled yet, so `remove` and `reset` never race expiry.
+pub struct DelayQueue<T> {
+    wheel: TimingWheel<T>,
+    // Expired but not yet polled, in expiry order
+    ready: VecDeque<Expired<T>>,
+}
+
+/// A value handed back by `DelayQueue`, with the key and deadline it was queued under
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expired<T> {
+    value: T,
+    key: Key,
+    deadline: u64,
+}
+
+impl<T> Expired<T> {
+    pub fn get_ref(&self) -> &T {
+        &self.value
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// The key `insert` (or the last `reset`) returned for this value
+    pub fn key(&self) -> Key {
+        self.key
+    }
+
+    /// The tick the value was due at
+    pub fn deadline(&self) -> u64 {
+        self.deadline
+    }
+}
+
+impl<T> Default for DelayQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> DelayQueue<T> {
+    pub fn new() -> Self {
+        Self {
+            wheel: TimingWheel::new(),
+            ready: VecDeque::new(),
+        }
+    }
+
+    /// Queues `value` to expire `ticks` ticks from now
+    pub fn insert(&mut self, value: T, ticks: u64) -> Key {
+        self.wheel.insert_in(value, ticks)
+    }
+
+    /// Queues `value` to expire at the absolute tick `deadline`
+    pub fn insert_at(&mut self, value: T, deadline: u64) -> Key {
+        self.wheel.insert(value, deadline)
+    }
+
+    /// Takes the value behind `key` out of the queue, whether or not it has expired yet.
+    /// Returns `None` once the value was handed back, or for a key from another queue.
+    pub fn remove(&mut self, key: &Key) -> Option<Expired<T>> {
+        if let Some(deadline) = self.wheel.deadline_of(*key) {
+            let value = self.wheel.cancel(*key)?;
+            return Some(Expired {
+                value,
+                key: *key,
+                deadline,
+            });
+        }
+        let pos = self.ready.iter().position(|expired| expired.key == *key)?;
+        self.ready.remove(pos)
+    }
+
+    /// Moves the value behind `key` to expire `ticks` ticks from now, returning the key to
+    /// use from then on. A value that expired but was not polled yet is queued again.
+    /// Returns `None` if the key is no longer valid.
+    pub fn reset(&mut self, key: &Key, ticks: u64) -> Option<Key> {
+        let deadline = self.wheel.current_time().saturating_add(ticks);
+        if self.wheel.contains(*key) {
+            return self.wheel.reschedule(*key, deadline);
+        }
+        // Expired values left the wheel, so they come back under a fresh key
+        let expired = self.remove(key)?;
+        Some(self.wheel.insert(expired.value, deadline))
+    }
+
+    /// Deadline of the value behind `key`, even if it has expired but not been polled
+    pub fn deadline(&self, key: &Key) -> Option<u64> {
+        self.wheel.deadline_of(*key).or_else(|| {
+            self.ready
+                .iter()
+                .find(|expired| expired.key == *key)
+                .map(Expired::deadline)
+        })
+    }
+
+    /// Moves the clock forward by `ticks`
+    pub fn advance(&mut self, ticks: u64) {
+        let target = self.wheel.current_time().saturating_add(ticks);
+        // Never behind the current tick, so this can't fail
+        let _ = self.set_time(target);
+    }
+
+    /// Moves the clock to `tick`. Fails without moving if `tick` is in the past.
+    pub fn set_time(&mut self, tick: u64) -> Result<(), ClockWentBackwards> {
+        let ready = &mut self.ready;
+        self.wheel.advance_to_with(tick, |key, deadline, value| {
+            ready.push_back(Expired {
+                value,
+                key,
+                deadline,
+            })
+        })
+    }
+
+    /// Next expired value, in the order they expired
+    pub fn poll_expired(&mut self) -> Option<Expired<T>> {
+        self.ready.pop_front()
+    }
+
+    pub fn current_time(&self) -> u64 {
+        self.wheel.current_time()
+    }
+
+    /// Values not handed back yet, expired or not
+    pub fn len(&self) -> usize {
+        self.wheel.len() + self.ready.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn poll_all<T>(queue: &mut DelayQueue<T>) -> Vec<T> {
+        core::iter::from_fn(|| queue.poll_expired())
+            .map(Expired::into_inner)
+            .collect()
+    }
+
+    #[test]
+    fn test_values_come_out_after_their_delay() {
+        let mut queue = DelayQueue::new();
+        let a = queue.insert("a", 3);
+        queue.insert("b", 1);
+        queue.insert_at("c", 100);
+
+        queue.advance(3);
+        assert_eq!(poll_all(&mut queue), vec!["b"]);
+        queue.advance(1);
+        let expired = queue.poll_expired().unwrap();
+        assert_eq!((expired.key(), expired.deadline()), (a, 3));
+        assert_eq!(expired.into_inner(), "a");
+
+        assert!(queue.set_time(50).is_ok());
+        assert!(queue.set_time(10).is_err());
+        assert_eq!(queue.current_time(), 50);
+        queue.set_time(101).unwrap();
+        assert_eq!(poll_all(&mut queue), vec!["c"]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_remove_before_and_after_expiry() {
+        let mut queue = DelayQueue::new();
+        let pending = queue.insert("pending", 50);
+        let expired = queue.insert("expired", 2);
+
+        queue.advance(10);
+        assert_eq!(queue.len(), 2);
+
+        // Expired but not polled: still removable, under its original deadline
+        let removed = queue.remove(&expired).unwrap();
+        assert_eq!(removed.deadline(), 2);
+        assert_eq!(removed.into_inner(), "expired");
+        assert_eq!(queue.deadline(&expired), None);
+        assert_eq!(
+            queue.remove(&pending).map(Expired::into_inner),
+            Some("pending")
+        );
+        assert_eq!(queue.remove(&pending), None);
+        assert_eq!(queue.remove(&expired), None);
+
+        queue.advance(100);
+        assert!(queue.poll_expired().is_none());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_polled_key_is_dead_even_after_its_slot_is_reused() {
+        let mut queue = DelayQueue::new();
+        let old = queue.insert("old", 1);
+        queue.advance(2);
+        assert_eq!(poll_all(&mut queue), vec!["old"]);
+
+        let new = queue.insert("new", 5);
+        assert_eq!(new.index(), old.index());
+        assert_eq!(queue.remove(&old), None);
+        assert_eq!(queue.reset(&old, 1), None);
+        assert_eq!(queue.deadline(&new), Some(7));
+    }
+
+    #[test]
+    fn test_reset_across_levels() {
+        let mut queue = DelayQueue::new();
+        let key = queue.insert("session", 10);
+
+        // Out to level 2, then back down to level 0 after a cascade to level 1
+        let key = queue.reset(&key, 5_000).unwrap();
+        assert_eq!(queue.deadline(&key), Some(5_000));
+        queue.advance(4_900);
+        assert!(queue.poll_expired().is_none());
+        let key = queue.reset(&key, 20).unwrap();
+        assert_eq!(queue.deadline(&key), Some(4_920));
+
+        queue.advance(20);
+        assert!(queue.poll_expired().is_none());
+        queue.advance(1);
+        assert_eq!(queue.poll_expired().unwrap().key(), key);
+    }
+
+    #[test]
+    fn test_reset_requeues_an_unpolled_expiry() {
+        let mut queue = DelayQueue::new();
+        let key = queue.insert("retry", 1);
+        queue.advance(5);
+        assert_eq!(queue.deadline(&key), Some(1));
+
+        let key = queue.reset(&key, 3).unwrap();
+        assert_eq!(queue.deadline(&key), Some(8));
+        assert!(queue.poll_expired().is_none());
+        assert_eq!(queue.len(), 1);
+
+        queue.advance(4);
+        let expired = queue.poll_expired().unwrap();
+        assert_eq!((expired.key(), expired.deadline()), (key, 8));
+    }
+}
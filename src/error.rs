@@ -0,0 +1,119 @@
+use core::fmt;
+
+/// Returned when the wheel is asked to move to a tick earlier than `current_time()`.
+///
+/// The wheel never rewinds: the request is rejected and the tick counter is left untouched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockWentBackwards {
+    /// How many ticks behind `current_time()` the requested target was
+    pub by: u64,
+}
+
+impl fmt::Display for ClockWentBackwards {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "clock went backwards by {} ticks", self.by)
+    }
+}
+
+impl core::error::Error for ClockWentBackwards {}
+
+/// Why `TimingWheel::try_insert` did not schedule a timer. The task is handed back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InsertError<T> {
+    /// The deadline is before `current_time()` and the wheel was built with
+    /// `FirePolicy::Reject`
+    DeadlineInPast(T),
+    /// The wheel already holds the maximum number of timers it was created with
+    Full(T),
+}
+
+impl<T> InsertError<T> {
+    /// The task that was not scheduled
+    pub fn into_task(self) -> T {
+        self.into_parts().1
+    }
+
+    /// The error without the task
+    fn kind(&self) -> WheelError {
+        match self {
+            InsertError::DeadlineInPast(_) => WheelError::DeadlineInPast,
+            InsertError::Full(_) => WheelError::Full,
+        }
+    }
+
+    /// Splits off the task, leaving an error that doesn't depend on `T`
+    pub fn into_parts(self) -> (WheelError, T) {
+        match self {
+            InsertError::DeadlineInPast(task) => (WheelError::DeadlineInPast, task),
+            InsertError::Full(task) => (WheelError::Full, task),
+        }
+    }
+}
+
+impl<T> From<InsertError<T>> for WheelError {
+    fn from(err: InsertError<T>) -> Self {
+        err.into_parts().0
+    }
+}
+
+impl<T> fmt::Display for InsertError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+impl<T: fmt::Debug> core::error::Error for InsertError<T> {}
+
+/// Why `TimingWheel::try_cancel` could not cancel a timer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CancelError {
+    /// The timer already fired or was cancelled. Expected when cancellation races expiry.
+    AlreadyCompleted,
+    /// The handle never named a timer of this wheel, which points at a bug in the caller
+    InvalidHandle,
+}
+
+impl fmt::Display for CancelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        WheelError::from(*self).fmt(f)
+    }
+}
+
+impl core::error::Error for CancelError {}
+
+impl From<CancelError> for WheelError {
+    fn from(err: CancelError) -> Self {
+        match err {
+            CancelError::AlreadyCompleted => WheelError::AlreadyCompleted,
+            CancelError::InvalidHandle => WheelError::InvalidHandle,
+        }
+    }
+}
+
+/// Any failure of a wheel operation, for callers that want one error type for every
+/// `try_` method. The specific errors convert into it, so `?` works across them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WheelError {
+    /// The deadline is before `current_time()` and the wheel rejects those; see
+    /// `FirePolicy`. Deadlines past the horizon are always accepted.
+    DeadlineInPast,
+    /// The wheel already holds the maximum number of timers it was created with
+    Full,
+    /// The handle never named a timer of this wheel
+    InvalidHandle,
+    /// The timer already fired or was cancelled
+    AlreadyCompleted,
+}
+
+impl fmt::Display for WheelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WheelError::DeadlineInPast => write!(f, "deadline is before the current tick"),
+            WheelError::Full => write!(f, "wheel holds its maximum number of timers"),
+            WheelError::InvalidHandle => write!(f, "handle does not belong to this wheel"),
+            WheelError::AlreadyCompleted => write!(f, "timer already fired or was cancelled"),
+        }
+    }
+}
+
+impl core::error::Error for WheelError {}
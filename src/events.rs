@@ -0,0 +1,61 @@
+use crate::slab::Key;
+use alloc::vec::Vec;
+
+/// Number of events kept by the `debug-events` log
+pub const EVENT_LOG_CAPACITY: usize = 256;
+
+/// A single operation recorded by the `debug-events` log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WheelEvent {
+    Insert {
+        handle: Key,
+        deadline: u64,
+        level: u8,
+    },
+    Cancel {
+        handle: Key,
+    },
+    /// `tick` is the value of `current_time()` when the tick started
+    Tick {
+        tick: u64,
+    },
+    Cascade {
+        handle: Key,
+        deadline: u64,
+        from_level: u8,
+        to_level: u8,
+    },
+    Expire {
+        handle: Key,
+        deadline: u64,
+    },
+}
+
+/// Bounded log of the most recent wheel operations.
+///
+/// Events are appended to a `Vec` that is trimmed back to `EVENT_LOG_CAPACITY` once it
+/// doubles in size. That keeps recording amortized O(1) while still letting
+/// `recent()` hand out one contiguous slice.
+pub(crate) struct EventLog {
+    events: Vec<WheelEvent>,
+}
+
+impl EventLog {
+    pub(crate) fn new() -> Self {
+        Self {
+            events: Vec::with_capacity(2 * EVENT_LOG_CAPACITY),
+        }
+    }
+
+    pub(crate) fn record(&mut self, event: WheelEvent) {
+        if self.events.len() == 2 * EVENT_LOG_CAPACITY {
+            self.events.drain(..EVENT_LOG_CAPACITY);
+        }
+        self.events.push(event);
+    }
+
+    pub(crate) fn recent(&self) -> &[WheelEvent] {
+        let start = self.events.len().saturating_sub(EVENT_LOG_CAPACITY);
+        &self.events[start..]
+    }
+}
@@ -0,0 +1,257 @@
+use crate::error::ClockWentBackwards;
+use crate::slab::Key;
+use crate::wheel::TimingWheel;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A timing wheel where every timer is identified by a caller-chosen key.
+///
+/// At most one timer is pending per key. The key travels with the task through
+/// the wheel, so expiry can drop the key from the index without a reverse lookup.
+pub struct KeyedTimingWheel<K, T> {
+    wheel: TimingWheel<(K, T)>,
+    // Live timers by key, with the deadline they are currently armed for
+    timers: HashMap<K, (Key, u64)>,
+}
+
+impl<K: Hash + Eq + Clone, T> Default for KeyedTimingWheel<K, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq + Clone, T> KeyedTimingWheel<K, T> {
+    pub fn new() -> Self {
+        Self {
+            wheel: TimingWheel::new(),
+            timers: HashMap::new(),
+        }
+    }
+
+    /// Schedules `task` for `key` unless a timer for `key` is already pending,
+    /// in which case that timer is moved to `deadline` only if it is earlier.
+    ///
+    /// Useful for "flush at the earliest time anyone asked for" patterns. When a timer
+    /// already exists its original task is kept and `task` is dropped.
+    /// Returns true if a timer was scheduled or moved.
+    pub fn schedule_earliest(&mut self, key: K, task: T, deadline: u64) -> bool {
+        match self.timers.get(&key) {
+            None => {
+                let handle = self.wheel.insert((key.clone(), task), deadline);
+                self.timers.insert(key, (handle, deadline));
+                true
+            }
+            Some(&(handle, current)) if deadline < current => {
+                let entry = self
+                    .wheel
+                    .cancel(handle)
+                    .expect("keyed index out of sync with wheel");
+                let handle = self.wheel.insert(entry, deadline);
+                self.timers.insert(key, (handle, deadline));
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    /// Schedules `task` for `key`, replacing any timer already pending for it.
+    /// Returns the task of the replaced timer.
+    pub fn insert_keyed(&mut self, key: K, task: T, deadline: u64) -> Option<T> {
+        let replaced = self.cancel_by_key(&key);
+        let handle = self.wheel.insert((key.clone(), task), deadline);
+        self.timers.insert(key, (handle, deadline));
+        replaced
+    }
+
+    /// Cancels the timer pending for `key`, returning its task
+    pub fn cancel_by_key(&mut self, key: &K) -> Option<T> {
+        let (handle, _) = self.timers.remove(key)?;
+        let (_, task) = self
+            .wheel
+            .cancel(handle)
+            .expect("keyed index out of sync with wheel");
+        Some(task)
+    }
+
+    /// Moves the timer pending for `key` to `deadline`, keeping its task. Returns false
+    /// if no timer is pending, e.g. because it already fired; nothing is scheduled then.
+    /// A deadline in the past is clamped to the current tick, as with `reschedule`.
+    pub fn reset_by_key(&mut self, key: &K, deadline: u64) -> bool {
+        let Some((handle, armed)) = self.timers.get_mut(key) else {
+            return false;
+        };
+        *handle = self
+            .wheel
+            .reschedule(*handle, deadline)
+            .expect("keyed index out of sync with wheel");
+        // Record what the wheel actually armed, which differs from `deadline` once clamped
+        *armed = self
+            .wheel
+            .deadline_of(*handle)
+            .expect("keyed index out of sync with wheel");
+        true
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.timers.contains_key(key)
+    }
+
+    /// Deadline of the timer pending for `key`, if any
+    pub fn deadline(&self, key: &K) -> Option<u64> {
+        self.timers.get(key).map(|&(_, deadline)| deadline)
+    }
+
+    /// Number of pending timers
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    /// Advances by one tick, pushing `(key, task)` for every expired timer
+    pub fn tick(&mut self, expired: &mut Vec<(K, T)>) {
+        let start = expired.len();
+        self.wheel.tick(expired);
+        self.forget_expired(&expired[start..]);
+    }
+
+    /// Advances to `target`, pushing `(key, task)` for every expired timer
+    pub fn advance_to(
+        &mut self,
+        target: u64,
+        expired: &mut Vec<(K, T)>,
+    ) -> Result<(), ClockWentBackwards> {
+        let start = expired.len();
+        let result = self.wheel.advance_to(target, expired);
+        self.forget_expired(&expired[start..]);
+        result
+    }
+
+    pub fn current_time(&self) -> u64 {
+        self.wheel.current_time()
+    }
+
+    fn forget_expired(&mut self, fired: &[(K, T)]) {
+        for (key, _) in fired {
+            self.timers.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_earliest_keeps_earliest_deadline() {
+        let mut wheel = KeyedTimingWheel::new();
+        let mut expired = Vec::new();
+
+        assert!(wheel.schedule_earliest("flush", 1, 50));
+        assert!(wheel.schedule_earliest("flush", 2, 20));
+        // Later than what's already armed: ignored
+        assert!(!wheel.schedule_earliest("flush", 3, 80));
+        assert_eq!(wheel.deadline(&"flush"), Some(20));
+        assert_eq!(wheel.len(), 1);
+
+        wheel.advance_to(20, &mut expired).unwrap();
+        assert!(expired.is_empty());
+
+        wheel.tick(&mut expired);
+        // Fires once, at the earliest requested tick, carrying the first task
+        assert_eq!(expired, vec![("flush", 1)]);
+        assert!(wheel.is_empty());
+
+        wheel.advance_to(100, &mut expired).unwrap();
+        assert_eq!(expired.len(), 1);
+    }
+
+    #[test]
+    fn test_insert_keyed_replaces_pending_timer() {
+        let mut wheel = KeyedTimingWheel::new();
+        let mut expired = Vec::new();
+
+        assert_eq!(wheel.insert_keyed("conn-1", "idle", 30), None);
+        // Re-inserting under the same key drops the old timer, earlier or later
+        assert_eq!(wheel.insert_keyed("conn-1", "busy", 10), Some("idle"));
+        assert_eq!(wheel.insert_keyed("conn-2", "idle", 20), None);
+        assert_eq!(wheel.len(), 2);
+
+        wheel.advance_to(40, &mut expired).unwrap();
+        assert_eq!(expired, vec![("conn-1", "busy"), ("conn-2", "idle")]);
+        assert!(!wheel.contains_key(&"conn-1"));
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn test_reset_to_past_deadline_records_clamped_tick() {
+        let mut wheel = KeyedTimingWheel::new();
+        let mut expired = Vec::new();
+
+        wheel.insert_keyed("conn", "idle", 200);
+        wheel.advance_to(100, &mut expired).unwrap();
+        assert!(wheel.reset_by_key(&"conn", 40));
+        assert_eq!(wheel.deadline(&"conn"), Some(100));
+
+        // Fires on the very next tick, like any other clamped reschedule
+        wheel.tick(&mut expired);
+        assert_eq!(expired, vec![("conn", "idle")]);
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn test_reset_and_cancel_by_key() {
+        let mut wheel = KeyedTimingWheel::new();
+        let mut expired = Vec::new();
+
+        wheel.insert_keyed(1u32, "a", 5);
+        wheel.insert_keyed(2, "b", 5);
+        assert!(wheel.reset_by_key(&1, 50));
+        assert_eq!(wheel.deadline(&1), Some(50));
+        assert_eq!(wheel.cancel_by_key(&2), Some("b"));
+        assert_eq!(wheel.cancel_by_key(&2), None);
+
+        wheel.advance_to(51, &mut expired).unwrap();
+        assert_eq!(expired, vec![(1, "a")]);
+
+        // Its timer already fired: resetting doesn't bring it back
+        assert!(!wheel.reset_by_key(&1, 100));
+        assert!(!wheel.contains_key(&1));
+        wheel.advance_to(200, &mut expired).unwrap();
+        assert_eq!(expired.len(), 1);
+    }
+
+    #[test]
+    fn test_schedule_earliest_rearms_after_expiry() {
+        let mut wheel = KeyedTimingWheel::new();
+        let mut expired = Vec::new();
+
+        wheel.schedule_earliest(7u32, "first", 5);
+        wheel.advance_to(6, &mut expired).unwrap();
+        assert_eq!(expired, vec![(7, "first")]);
+
+        // The key is free again once its timer fired
+        assert!(wheel.schedule_earliest(7, "second", 90));
+        assert_eq!(wheel.deadline(&7), Some(90));
+    }
+
+    #[test]
+    fn test_tick_forgets_only_the_expired_keys() {
+        let mut wheel = KeyedTimingWheel::new();
+        let mut expired = Vec::new();
+
+        wheel.insert_keyed("a", 1, 2);
+        wheel.insert_keyed("b", 2, 2);
+        wheel.insert_keyed("b", 3, 9);
+        for _ in 0..3 {
+            wheel.tick(&mut expired);
+        }
+        assert_eq!(expired, vec![("a", 1)]);
+        assert!(!wheel.contains_key(&"a"));
+        assert_eq!(wheel.cancel_by_key(&"a"), None);
+        assert_eq!(wheel.cancel_by_key(&"b"), Some(3));
+        assert!(wheel.is_empty());
+    }
+}
@@ -1,4 +1,49 @@
+//! Hierarchical Timing Wheel Implementation
+//! Based on Varghese and Lauck's paper
+//! "Hashed and Hierarchical Timing Wheels: Efficient Data Structures for Implementing a Timer Facility"
+//!
+//! The core wheel only needs `alloc`. Disable the default `std` feature to build it as
+//! `no_std`; that drops the wall-clock helpers and the hash-based wrappers.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+pub mod builder;
+pub mod callback;
+#[cfg(feature = "std")]
+pub mod clock;
+pub mod config;
+pub mod delay_queue;
+pub mod error;
+#[cfg(feature = "debug-events")]
+pub mod events;
+#[cfg(feature = "std")]
+pub mod keyed;
+pub mod observer;
+#[cfg(feature = "serde")]
+mod persist;
+#[cfg(feature = "std")]
+pub mod sharded;
 pub mod slab;
+#[cfg(feature = "std")]
+pub mod sync;
 pub mod wheel;
 
-use crate::wheel::TimingWheel;
+pub use crate::builder::WheelBuilder;
+pub use crate::callback::CallbackWheel;
+#[cfg(feature = "std")]
+pub use crate::clock::{Clock, MockClock, SystemClock};
+pub use crate::config::WheelConfig;
+pub use crate::delay_queue::{DelayQueue, Expired};
+pub use crate::error::{CancelError, ClockWentBackwards, InsertError, WheelError};
+#[cfg(feature = "debug-events")]
+pub use crate::events::WheelEvent;
+#[cfg(feature = "std")]
+pub use crate::keyed::KeyedTimingWheel;
+pub use crate::observer::WheelObserver;
+#[cfg(feature = "std")]
+pub use crate::sharded::ShardedWheel;
+pub use crate::slab::{Key, SlabStrategy};
+#[cfg(feature = "std")]
+pub use crate::sync::SyncTimingWheel;
+pub use crate::wheel::{FirePolicy, InsertPrecision, TimerGuard, TimingWheel, WheelStats};
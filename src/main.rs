@@ -1,9 +1,4 @@
-/// Hierarchical Timing Wheel Implementation
-/// Based on Varghese and Lauck's paper
-/// "Hashed and Hierarchical Timing Wheels: Efficient Data Structures for Implementing a Timer Facility"
-mod slab;
-mod wheel;
-use crate::wheel::TimingWheel;
+use sharded_timing_wheel::TimingWheel;
 use std::time::Instant;
 
 fn main() {
@@ -18,8 +13,8 @@ fn main() {
     // Schedule 100,000 timers with random-ish deadlines
     // to simulate network timeouts ranging from 1ms to 10,000ms
     for i in 0..num_timers {
-        let deadline = (i as u64 % 10_000) + 1; // Deadline between 1 and 10,000 ticks
-        wheel.insert(format!("Request-{}", i), deadline);
+        let delay = (i as u64 % 10_000) + 1; // Between 1 and 10,000 ticks from now
+        wheel.insert_in(format!("Request-{}", i), delay);
     }
 
     let insert_time = start_insert.elapsed();
@@ -41,7 +36,12 @@ fn main() {
     // Run ticks until all timers have expired
     while total_expired < num_timers {
         // Pass the buffer to be filled
-        wheel.tick(&mut expired);
+        wheel.tick_deadlines(&mut expired);
+
+        // Nothing may fire before its deadline
+        for (deadline, _task) in &expired {
+            assert!(*deadline < wheel.current_time(), "fired early");
+        }
 
         // Count how many expired in this tick
         total_expired += expired.len();
@@ -0,0 +1,22 @@
+use crate::slab::Key;
+
+/// Hooks for watching a wheel from the outside, e.g. to drive a live countdown UI.
+///
+/// Every method has an empty default so observers only implement what they need.
+/// Callbacks run synchronously inside the wheel operation that triggered them.
+pub trait WheelObserver: Send {
+    /// A pending timer's deadline was moved
+    fn on_reschedule(&mut self, _id: Key, _old_deadline: u64, _new_deadline: u64) {}
+
+    /// A bulk operation moved `count` timers at once. Emitted instead of one
+    /// `on_reschedule` per timer so whole-wheel rewrites stay cheap to observe.
+    fn on_bulk_reschedule(&mut self, _count: usize) {}
+
+    /// A timer was routed to the overflow set because its deadline lies beyond the
+    /// horizon of the wheels. It re-enters the wheels, silently, once it is in range.
+    fn on_overflow_parked(&mut self, _id: Key) {}
+
+    /// The slab released storage, going from `old_capacity` to `new_capacity` slots.
+    /// Only emitted by the auto-shrink policy; live handles are unaffected.
+    fn on_slab_shrink(&mut self, _old_capacity: usize, _new_capacity: usize) {}
+}
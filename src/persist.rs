@@ -0,0 +1,267 @@
+//! `serde` support for snapshotting a wheel, e.g. for crash recovery.
+//!
+//! A wheel is written as its `current_tick` plus a flat list of timers, each with its
+//! deadline, its task and what it was inserted with: priority, group, period and
+//! precision. Slab indices, generations and bucket positions are not part of the
+//! format: deserializing builds a fresh default wheel at the saved tick and re-inserts
+//! every timer, so handles from before the snapshot do not resolve in the restored
+//! wheel. Older snapshots listing bare `(deadline, task)` pairs still load, as plain
+//! timers. Snapshots with periodic timers need `TimingWheel::deserialize_periodic`,
+//! which requires `T: Clone`.
+
+use crate::slab::TimerEntry;
+use crate::wheel::TimingWheel;
+use alloc::vec::Vec;
+use core::num::NonZeroU64;
+use serde::de::{Deserialize, Deserializer, Error as _};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+/// One pending timer as written to a snapshot
+#[derive(serde::Serialize)]
+#[serde(rename = "Timer")]
+struct TimerRef<'a, T> {
+    deadline: u64,
+    task: &'a T,
+    priority: u8,
+    group: Option<u32>,
+    interval: Option<NonZeroU64>,
+    approximate: bool,
+}
+
+impl<'a, T> From<&'a TimerEntry<T>> for TimerRef<'a, T> {
+    fn from(entry: &'a TimerEntry<T>) -> Self {
+        Self {
+            deadline: entry.deadline,
+            task: &entry.task,
+            priority: entry.priority,
+            group: entry.group,
+            interval: entry.interval,
+            approximate: entry.approximate,
+        }
+    }
+}
+
+/// The pending timers of a wheel, serialized as a sequence without collecting them first
+struct Timers<'a, T>(&'a TimingWheel<T>);
+
+impl<T: Serialize> Serialize for Timers<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.entries().map(TimerRef::from))
+    }
+}
+
+impl<T: Serialize> Serialize for TimingWheel<T> {
+    /// Timers are written in unspecified order. The resolution, the level config, the
+    /// builder's policies (fire policy, timer limit, auto-shrink, slab strategy), the
+    /// observer and the stats are not saved.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("TimingWheel", 2)?;
+        state.serialize_field("current_tick", &self.current_time())?;
+        state.serialize_field("timers", &Timers(self))?;
+        state.end()
+    }
+}
+
+/// A timer read back from a snapshot. Only the first two fields are required, so an
+/// old `(deadline, task)` pair reads as a plain timer.
+#[derive(serde::Deserialize)]
+#[serde(rename = "Timer")]
+struct SavedTimer<T> {
+    deadline: u64,
+    task: T,
+    #[serde(default)]
+    priority: u8,
+    #[serde(default)]
+    group: Option<u32>,
+    #[serde(default)]
+    interval: Option<NonZeroU64>,
+    #[serde(default)]
+    approximate: bool,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename = "TimingWheel")]
+struct Snapshot<T> {
+    current_tick: u64,
+    timers: Vec<SavedTimer<T>>,
+}
+
+impl<T> Snapshot<T> {
+    /// Moves `wheel`, fresh and empty, to the saved tick and re-inserts every timer
+    fn into_wheel(self, mut wheel: TimingWheel<T>) -> TimingWheel<T> {
+        // An empty wheel has nothing to fire, so this only moves the counter
+        let _ = wheel.advance_to(self.current_tick, &mut Vec::new());
+        for timer in self.timers {
+            wheel.restore(
+                timer.task,
+                timer.deadline,
+                timer.priority,
+                timer.group,
+                timer.interval,
+                timer.approximate,
+            );
+        }
+        wheel
+    }
+
+    fn has_periodic(&self) -> bool {
+        self.timers.iter().any(|timer| timer.interval.is_some())
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for TimingWheel<T> {
+    /// Rebuilds a default wheel at the saved tick. Timers that were overdue when the
+    /// snapshot was taken fire on the first tick after the restore.
+    ///
+    /// A periodic timer hands out a clone of its task on every firing, which needs
+    /// `T: Clone`, so a snapshot holding one is refused here; restore it with
+    /// [`TimingWheel::deserialize_periodic`] instead.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let snapshot = Snapshot::<T>::deserialize(deserializer)?;
+        if snapshot.has_periodic() {
+            return Err(D::Error::custom(
+                "snapshot holds periodic timers; restore it with `deserialize_periodic`",
+            ));
+        }
+        Ok(snapshot.into_wheel(TimingWheel::new()))
+    }
+}
+
+impl<T: Clone> TimingWheel<T> {
+    /// `Deserialize`, also accepting snapshots with periodic timers, which come back
+    /// periodic and carry on from their saved next deadline
+    pub fn deserialize_periodic<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let snapshot = Snapshot::<T>::deserialize(deserializer)?;
+        let mut wheel = TimingWheel::new();
+        wheel.enable_periodic();
+        Ok(snapshot.into_wheel(wheel))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Drains `wheel`, recording each task with the tick it fired on
+    fn fire_times(wheel: &mut TimingWheel<String>) -> Vec<(u64, String)> {
+        let mut fired = Vec::new();
+        let mut expired = Vec::new();
+        while let Some(deadline) = wheel.peek_next_deadline() {
+            let tick = deadline.max(wheel.current_time());
+            wheel.advance_to(tick, &mut expired).unwrap();
+            assert!(expired.is_empty(), "fired before tick {tick}");
+            wheel.tick(&mut expired);
+            expired.sort();
+            fired.extend(expired.drain(..).map(|task| (tick, task)));
+        }
+        fired
+    }
+
+    #[test]
+    fn test_round_trip_fires_same_tasks_on_same_ticks() {
+        let mut original = TimingWheel::new();
+        // Level 0, upper levels, a bucket shared by two timers and the overflow set
+        for (deadline, task) in [
+            (10, "a"),
+            (75, "b"),
+            (75, "c"),
+            (5_000, "d"),
+            (300_000, "e"),
+            ((1 << 24) + 9, "f"),
+        ] {
+            original.insert(task.to_string(), deadline);
+        }
+        let cancelled = original.insert("cancelled".to_string(), 80);
+        original.cancel(cancelled);
+        original.advance_to(50, &mut Vec::new()).unwrap();
+
+        let json = serde_json::to_string(&original).unwrap();
+        let mut restored: TimingWheel<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.current_time(), 50);
+        assert_eq!(restored.len(), 5);
+
+        let expected = fire_times(&mut original);
+        assert_eq!(expected.len(), 5);
+        assert_eq!(fire_times(&mut restored), expected);
+    }
+
+    #[test]
+    fn test_round_trip_keeps_priority_group_and_period() {
+        let mut original = TimingWheel::new();
+        original.insert_periodic("heartbeat".to_string(), 10, 25);
+        original.insert_grouped("session-a".to_string(), 60, 7);
+        original.insert_grouped("session-b".to_string(), 90, 7);
+        original.insert_with_priority("low".to_string(), 40, 1);
+        original.insert_with_priority("high".to_string(), 40, 9);
+        let mut expired = Vec::new();
+        original.advance_to(20, &mut expired).unwrap();
+        assert_eq!(expired, vec!["heartbeat"]);
+
+        let json = serde_json::to_string(&original).unwrap();
+        // Plain `Deserialize` can't re-arm the heartbeat without `T: Clone`
+        assert!(serde_json::from_str::<TimingWheel<String>>(&json).is_err());
+        let mut restored = TimingWheel::<String>::deserialize_periodic(
+            &mut serde_json::Deserializer::from_str(&json),
+        )
+        .unwrap();
+        assert_eq!(restored.len(), 5);
+
+        // The group can still be cancelled as a whole
+        let mut sessions = restored.cancel_group(7);
+        sessions.sort();
+        assert_eq!(sessions, vec!["session-a", "session-b"]);
+
+        // The period carries on from the re-armed deadline, and priorities still order
+        // the shared tick
+        let mut fired = Vec::new();
+        while restored.current_time() <= 85 {
+            let tick = restored.current_time();
+            expired.clear();
+            restored.tick(&mut expired);
+            fired.extend(expired.drain(..).map(|task| (tick, task)));
+        }
+        let fired: Vec<(u64, &str)> = fired.iter().map(|(t, s)| (*t, s.as_str())).collect();
+        assert_eq!(
+            fired,
+            vec![
+                (35, "heartbeat"),
+                (40, "high"),
+                (40, "low"),
+                (60, "heartbeat"),
+                (85, "heartbeat"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_non_clone_tasks_restore_without_periodic_timers() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Job(u32);
+
+        let mut original = TimingWheel::new();
+        original.insert_grouped(Job(1), 30, 2);
+        original.insert_with_priority(Job(2), 30, 5);
+        let json = serde_json::to_string(&original).unwrap();
+
+        let mut restored: TimingWheel<Job> = serde_json::from_str(&json).unwrap();
+        let mut expired = Vec::new();
+        restored.advance_to(31, &mut expired).unwrap();
+        assert_eq!(expired, vec![Job(2), Job(1)]);
+    }
+
+    #[test]
+    fn test_overdue_timers_fire_right_after_restore() {
+        let json = r#"{"current_tick":100,"timers":[[40,"late"],[101,"next"]]}"#;
+        let mut restored: TimingWheel<String> = serde_json::from_str(json).unwrap();
+
+        let mut expired = Vec::new();
+        restored.tick(&mut expired);
+        assert_eq!(expired, vec!["late"]);
+        restored.tick(&mut expired);
+        assert_eq!(expired, vec!["late", "next"]);
+    }
+}
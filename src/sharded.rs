@@ -0,0 +1,292 @@
+use crate::error::{ClockWentBackwards, InsertError};
+use crate::slab::Key;
+use crate::wheel::TimingWheel;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroU32;
+
+// The shard id lives in the high bits of a handle's slab index
+const SHARD_SHIFT: u32 = 24;
+const LOCAL_MASK: u32 = (1 << SHARD_SHIFT) - 1;
+
+/// Maximum number of shards a `ShardedWheel` can be split into
+pub const MAX_SHARDS: usize = 1 << (u32::BITS - SHARD_SHIFT);
+
+/// A set of independent timing wheels, with timers spread across them by key.
+///
+/// Each shard has its own slab and tick state, so shards can later be driven from
+/// different threads. Handles returned by `insert` carry the shard id in the high
+/// bits of their index, which lets `cancel` route straight to the owning shard.
+/// Each shard holds at most 2^24 timers.
+pub struct ShardedWheel<T> {
+    shards: Vec<TimingWheel<T>>,
+    // Highest local index a shard may hand out; only lowered by tests
+    local_limit: u32,
+}
+
+impl<T> ShardedWheel<T> {
+    pub fn new(num_shards: usize) -> Self {
+        assert!(
+            (1..=MAX_SHARDS).contains(&num_shards),
+            "shard count must be between 1 and {MAX_SHARDS}"
+        );
+        Self {
+            shards: (0..num_shards).map(|_| TimingWheel::new()).collect(),
+            local_limit: LOCAL_MASK,
+        }
+    }
+
+    pub fn num_shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// The shard a key hashes to
+    pub fn shard_for<K: Hash + ?Sized>(&self, key: &K) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.shards.len() as u64) as usize
+    }
+
+    /// The shard that owns a handle returned by this wheel
+    pub fn shard_of(key: Key) -> usize {
+        (key.index().get() >> SHARD_SHIFT) as usize
+    }
+
+    /// Inserts on the shard chosen by hashing `key`
+    pub fn insert<K: Hash + ?Sized>(&mut self, key: &K, task: T, deadline: u64) -> Key {
+        let shard = self.shard_for(key);
+        self.insert_on(shard, task, deadline)
+    }
+
+    /// Inserts on an explicitly chosen shard
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shard already holds 2^24 timers.
+    pub fn insert_on(&mut self, shard: usize, task: T, deadline: u64) -> Key {
+        assert!(!self.is_shard_full(shard), "shard {shard} is full");
+        let local = self.shards[shard].insert(task, deadline);
+        self.global_key(shard, local)
+    }
+
+    /// `insert_on` that hands the task back instead of panicking when the shard is full
+    pub fn try_insert_on(
+        &mut self,
+        shard: usize,
+        task: T,
+        deadline: u64,
+    ) -> Result<Key, InsertError<T>> {
+        if self.is_shard_full(shard) {
+            return Err(InsertError::Full(task));
+        }
+        let local = self.shards[shard].try_insert(task, deadline)?;
+        Ok(self.global_key(shard, local))
+    }
+
+    /// Whether the next insert on `shard` would need an index the handle can't encode.
+    /// Checked before inserting, so a full shard never ends up holding an unreachable timer.
+    fn is_shard_full(&self, shard: usize) -> bool {
+        self.shards[shard].vacant_key().index().get() > self.local_limit
+    }
+
+    /// Tags a handle issued by `shard` with the shard id
+    fn global_key(&self, shard: usize, local: Key) -> Key {
+        let global = NonZeroU32::new(((shard as u32) << SHARD_SHIFT) | local.index().get());
+        Key::new(local.owner(), global.unwrap(), local.generation())
+    }
+
+    /// `TimingWheel::insert_periodic` on an explicitly chosen shard
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shard is full, or under the same conditions as `insert_periodic`.
+    pub fn insert_periodic_on(
+        &mut self,
+        shard: usize,
+        task: T,
+        first_deadline: u64,
+        interval: u64,
+    ) -> Key
+    where
+        T: Clone,
+    {
+        assert!(!self.is_shard_full(shard), "shard {shard} is full");
+        let local = self.shards[shard].insert_periodic(task, first_deadline, interval);
+        self.global_key(shard, local)
+    }
+
+    /// `TimingWheel::reschedule` on the shard that owns `key`. The returned handle is
+    /// `key` itself, as on a single wheel.
+    pub fn reschedule(&mut self, key: Key, new_deadline: u64) -> Option<Key> {
+        let shard = self.shards.get_mut(Self::shard_of(key))?;
+        shard.reschedule(Self::local_key(key), new_deadline)?;
+        Some(key)
+    }
+
+    /// `TimingWheel::touch` on the shard that owns `key`
+    pub fn touch(&mut self, key: Key, extra: u64) -> Option<u64> {
+        let shard = self.shards.get_mut(Self::shard_of(key))?;
+        shard.touch(Self::local_key(key), extra)
+    }
+
+    pub fn cancel(&mut self, key: Key) -> Option<T> {
+        let shard = self.shards.get_mut(Self::shard_of(key))?;
+        shard.cancel(Self::local_key(key))
+    }
+
+    pub fn shard(&self, shard: usize) -> &TimingWheel<T> {
+        &self.shards[shard]
+    }
+
+    pub fn shard_mut(&mut self, shard: usize) -> &mut TimingWheel<T> {
+        &mut self.shards[shard]
+    }
+
+    /// Ticks every shard once, appending their expirations shard by shard
+    pub fn tick(&mut self, expired: &mut Vec<T>) {
+        for shard in &mut self.shards {
+            shard.tick(expired);
+        }
+    }
+
+    /// Advances every shard to `target`. Fails without moving anything if any shard is
+    /// already past it.
+    pub fn advance_to(
+        &mut self,
+        target: u64,
+        expired: &mut Vec<T>,
+    ) -> Result<(), ClockWentBackwards> {
+        if let Some(ahead) = self.shards.iter().map(TimingWheel::current_time).max()
+            && ahead > target
+        {
+            return Err(ClockWentBackwards { by: ahead - target });
+        }
+        for shard in &mut self.shards {
+            shard.advance_to(target, expired)?;
+        }
+        Ok(())
+    }
+
+    /// Strips the shard id, giving the handle the shard itself issued
+    fn local_key(key: Key) -> Key {
+        let local = NonZeroU32::new(key.index().get() & LOCAL_MASK);
+        // An all-zero local index can't come from us; map it to a slot that won't resolve
+        Key::new(
+            key.owner(),
+            local.unwrap_or(NonZeroU32::MAX),
+            key.generation(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handles_route_to_their_shard() {
+        let mut wheel = ShardedWheel::new(4);
+
+        let handles: Vec<Key> = (0..4)
+            .map(|shard| wheel.insert_on(shard, shard, 10))
+            .collect();
+        for (shard, &handle) in handles.iter().enumerate() {
+            assert_eq!(ShardedWheel::<usize>::shard_of(handle), shard);
+            assert_eq!(
+                wheel
+                    .shard(shard)
+                    .deadline_of(ShardedWheel::<usize>::local_key(handle)),
+                Some(10)
+            );
+        }
+
+        // Each shard numbers its slots independently, yet the handles never collide
+        assert_eq!(wheel.cancel(handles[2]), Some(2));
+        assert_eq!(wheel.cancel(handles[2]), None);
+        assert!(
+            wheel
+                .shard(3)
+                .contains(ShardedWheel::<usize>::local_key(handles[3]))
+        );
+    }
+
+    #[test]
+    fn test_expiry_order_is_preserved_per_shard() {
+        let mut wheel = ShardedWheel::new(8);
+        let mut expired = Vec::new();
+
+        let connections: Vec<String> = (0..64).map(|i| format!("conn-{i}")).collect();
+        for (i, conn) in connections.iter().enumerate() {
+            let deadline = 1 + (i as u64 * 37) % 200;
+            wheel.insert(conn, (wheel.shard_for(conn), deadline), deadline);
+        }
+        // Hashing spreads the keys over more than one shard
+        let used: std::collections::HashSet<usize> =
+            connections.iter().map(|c| wheel.shard_for(c)).collect();
+        assert!(used.len() > 1);
+
+        let mut fired = Vec::new();
+        while wheel.shard(0).current_time() <= 200 {
+            wheel.tick(&mut expired);
+            fired.append(&mut expired);
+        }
+        assert_eq!(fired.len(), connections.len());
+
+        for shard in 0..8 {
+            let deadlines: Vec<u64> = fired
+                .iter()
+                .filter(|(s, _)| *s == shard)
+                .map(|&(_, deadline)| deadline)
+                .collect();
+            assert!(deadlines.windows(2).all(|w| w[0] <= w[1]));
+        }
+    }
+
+    #[test]
+    fn test_full_shard_refuses_inserts_without_keeping_them() {
+        let mut wheel = ShardedWheel::new(2);
+        wheel.local_limit = 3;
+
+        let handles: Vec<Key> = (0..3).map(|i| wheel.insert_on(0, i, 10)).collect();
+        assert_eq!(wheel.try_insert_on(0, 3, 10), Err(InsertError::Full(3)));
+        assert_eq!(wheel.shard(0).len(), 3);
+
+        // The other shard is unaffected, and a freed slot makes room again
+        assert!(wheel.try_insert_on(1, 4, 10).is_ok());
+        assert_eq!(wheel.cancel(handles[1]), Some(1));
+        let reused = wheel.try_insert_on(0, 5, 10).unwrap();
+        assert_eq!(wheel.cancel(reused), Some(5));
+    }
+
+    #[test]
+    #[should_panic(expected = "shard 0 is full")]
+    fn test_insert_on_full_shard_panics() {
+        let mut wheel = ShardedWheel::new(1);
+        wheel.local_limit = 1;
+        wheel.insert_on(0, "a", 10);
+        wheel.insert_on(0, "b", 10);
+    }
+
+    #[test]
+    fn test_touch_and_reschedule_route_to_their_shard() {
+        let mut wheel = ShardedWheel::new(3);
+        let mut expired = Vec::new();
+        let key = wheel.insert_on(2, "a", 10);
+        wheel.insert_on(0, "b", 10);
+
+        assert_eq!(wheel.touch(key, 30), Some(30));
+        assert_eq!(wheel.reschedule(key, 40), Some(key));
+        wheel.advance_to(40, &mut expired).unwrap();
+        assert_eq!(expired, vec!["b"]);
+        wheel.tick(&mut expired);
+        assert_eq!(expired, vec!["b", "a"]);
+        assert_eq!(wheel.touch(key, 5), None);
+    }
+
+    #[test]
+    fn test_cancel_with_unknown_shard() {
+        let mut wheel: ShardedWheel<()> = ShardedWheel::new(2);
+        let bogus = Key::new(0, NonZeroU32::new(5 << SHARD_SHIFT | 1).unwrap(), 0);
+        assert_eq!(wheel.cancel(bogus), None);
+    }
+}
@@ -1,8 +1,17 @@
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::fmt;
+use core::marker::PhantomData;
+use core::num::{NonZeroU32, NonZeroU64};
 use core::panic;
-use std::num::NonZeroU32;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+// Source of per-slab owner ids. Wrapping after 2^32 slabs is harmless: a collision only
+// weakens the cross-wheel check back to the generation check.
+static NEXT_OWNER: AtomicU32 = AtomicU32::new(0);
 
 /// A Timer Entry stored in the slab allocator
-#[derive(Debug)]
 pub struct TimerEntry<T> {
     pub task: T,
     pub deadline: u64,
@@ -10,6 +19,11 @@ pub struct TimerEntry<T> {
     pub next: Option<NonZeroU32>, // Index of the next TimerEntry in the slab
     pub prev: Option<NonZeroU32>, // Index of the previous TimerEntry in the slab
     pub level: u8,                // Changed from usize to u8 for efficiency
+    pub slot: u8,                 // Bucket within `level`, recorded when linked
+    pub approximate: bool,        // Fire when an upper bucket is reached instead of cascading
+    pub group: Option<u32>,       // Set by `insert_grouped`, for `cancel_group`
+    pub priority: u8,             // Same-tick delivery order, highest first
+    pub interval: Option<NonZeroU64>, // Re-armed this far out on expiry, see `insert_periodic`
 }
 
 enum Entry<T> {
@@ -17,110 +31,549 @@ enum Entry<T> {
     Free(Option<NonZeroU32>), // Points to the next free entry
 }
 
+/// Stand-in printed in place of a task, so `Debug` works whatever `T` is.
+/// Shows the task's type name instead of its value.
+struct Redacted<T>(PhantomData<T>);
+
+impl<T> fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<redacted {}>", core::any::type_name::<T>())
+    }
+}
+
+// Manual impls: deriving would require `T: Debug` for the wheel to be printable at all
+impl<T> fmt::Debug for TimerEntry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimerEntry")
+            .field("task", &Redacted::<T>(PhantomData))
+            .field("deadline", &self.deadline)
+            .field("next", &self.next)
+            .field("prev", &self.prev)
+            .field("level", &self.level)
+            .field("slot", &self.slot)
+            .field("approximate", &self.approximate)
+            .field("group", &self.group)
+            .field("priority", &self.priority)
+            .field("interval", &self.interval)
+            .finish()
+    }
+}
+
+impl<T> fmt::Debug for Entry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Occupied(entry) => f.debug_tuple("Occupied").field(entry).finish(),
+            Entry::Free(next) => f.debug_tuple("Free").field(next).finish(),
+        }
+    }
+}
+
+/// A slab slot. The generation is bumped every time the slot is freed, so a `Key`
+/// handed out before the free can no longer resolve to whatever reuses the slot.
+struct Slot<T> {
+    generation: u32,
+    entry: Entry<T>,
+}
+
+impl<T> fmt::Debug for Slot<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "gen {}: {:?}", self.generation, self.entry)
+    }
+}
+
+/// Handle to a slab entry: the 1-based slot index plus the slot's generation at allocation,
+/// tagged with an id of the slab that issued it.
+///
+/// The owner id makes a handle from one wheel resolve to nothing in another, instead of
+/// naming whatever timer happens to sit in the same slot there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Key {
+    owner: u32,
+    index: NonZeroU32,
+    generation: u32,
+}
+
+impl Key {
+    pub(crate) fn new(owner: u32, index: NonZeroU32, generation: u32) -> Self {
+        Self {
+            owner,
+            index,
+            generation,
+        }
+    }
+
+    /// Rebuilds a handle from the parts returned by `owner`, `index` and `generation`,
+    /// e.g. after passing it through FFI as plain integers. A fabricated handle is safe
+    /// to use: if it doesn't match a live timer, every lookup simply fails.
+    pub fn from_raw(owner: u32, index: NonZeroU32, generation: u32) -> Self {
+        Self::new(owner, index, generation)
+    }
+
+    /// Id of the slab, and so of the wheel, that issued this handle
+    pub fn owner(&self) -> u32 {
+        self.owner
+    }
+
+    /// 1-based slot index in the slab
+    pub fn index(&self) -> NonZeroU32 {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// Which free slot the slab hands out next.
+///
+/// `Lifo` reuses the most recently freed slot in O(1). After heavy churn the live entries
+/// end up scattered over the whole storage, so walks over every timer (`iter`, `retain`,
+/// `cancel_group`) touch more memory than the live count suggests, and `shrink_to` finds
+/// little free tail to release.
+///
+/// `LowestIndex` keeps the free slots in a min-heap and always reuses the lowest one, so
+/// live entries cluster at the front of the storage. Each alloc and free then costs
+/// O(log f) for f free slots, plus the heap's own memory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SlabStrategy {
+    #[default]
+    Lifo,
+    LowestIndex,
+}
+
 /// Slab Allocator for cache friendly memory layout
 pub struct Slab<T> {
-    entries: Vec<Entry<T>>,
+    entries: Vec<Slot<T>>,
     next_free: Option<NonZeroU32>,
+    strategy: SlabStrategy,
+    // Free slots under `LowestIndex`, in place of the `next_free` list
+    lowest_free: BinaryHeap<Reverse<NonZeroU32>>,
+    // Number of occupied slots
+    len: usize,
+    // Generation for slots pushed from now on. Raised past every slot dropped by a shrink,
+    // so a key into a released slot cannot match whatever is allocated there later.
+    fresh_generation: u32,
+    // Stamped into every key, see `Key::owner`
+    owner: u32,
+}
+
+impl<T> fmt::Debug for Slab<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Slab")
+            .field("capacity", &self.entries.capacity())
+            .field("next_free", &self.next_free)
+            .field("strategy", &self.strategy)
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
+
+impl<T> Default for Slab<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Consumes the slab, yielding `(deadline, task)` for every occupied slot in slot order
+impl<T> IntoIterator for Slab<T> {
+    type Item = (u64, T);
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter {
+            remaining: self.len,
+            slots: self.entries.into_iter(),
+        }
+    }
+}
+
+/// Owning iterator over a slab's live entries, see `Slab::into_iter`
+pub struct IntoIter<T> {
+    slots: alloc::vec::IntoIter<Slot<T>>,
+    remaining: usize,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = (u64, T);
+
+    fn next(&mut self) -> Option<(u64, T)> {
+        let next = self.slots.find_map(|slot| match slot.entry {
+            Entry::Occupied(entry) => Some((entry.deadline, entry.task)),
+            Entry::Free(_) => None,
+        })?;
+        self.remaining -= 1;
+        Some(next)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
 }
 
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
 impl<T> Slab<T> {
     pub fn new() -> Self {
+        Self::with_capacity(1024) // Preallocate some space
+    }
+
+    /// Creates a slab that can hold `capacity` entries before it reallocates
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_strategy(capacity, SlabStrategy::Lifo)
+    }
+
+    /// `with_capacity`, reusing free slots in the order `strategy` picks
+    pub fn with_strategy(capacity: usize, strategy: SlabStrategy) -> Self {
         Self {
-            entries: Vec::with_capacity(1024), // Preallocate some space
-            next_free: None,                   // No free entries initially
+            entries: Vec::with_capacity(capacity),
+            next_free: None, // No free entries initially
+            strategy,
+            lowest_free: BinaryHeap::new(),
+            len: 0,
+            fresh_generation: 0,
+            owner: NEXT_OWNER.fetch_add(1, Ordering::Relaxed),
         }
     }
 
+    /// Bytes of entry storage this slab holds, by capacity. Computed from the `Vec`'s
+    /// capacity rather than tracked at the allocator, so allocator overhead is not counted.
+    #[cfg(feature = "alloc-tagging")]
+    pub fn heap_bytes(&self) -> usize {
+        self.entries.capacity() * size_of::<Slot<T>>()
+    }
+
     /// Allocate a new entry, resusing freed slots if available
-    pub fn alloc(&mut self, task: T, deadline: u64, level: u8) -> NonZeroU32 {
+    pub fn alloc(&mut self, task: T, deadline: u64, level: u8) -> Key {
         let entry = TimerEntry {
             task,
             deadline,
             next: None,
             prev: None,
             level,
+            slot: 0,
+            approximate: false,
+            group: None,
+            priority: 0,
+            interval: None,
         };
 
-        if let Some(idx) = self.next_free {
+        self.len += 1;
+        if let Some(idx) = self.pop_free() {
             // Reuse a free slot
             // Convert 1-based NonZeroU32 to 0-based usize
-            let vec_idx = (idx.get() - 1) as usize;
-
-            match self.entries[vec_idx] {
-                Entry::Free(next_idx) => {
-                    self.next_free = next_idx;
-                }
-                _ => panic!("Corrupted free list"),
-            }
-            self.entries[vec_idx] = Entry::Occupied(entry);
-            return idx;
+            let slot = &mut self.entries[(idx.get() - 1) as usize];
+            slot.entry = Entry::Occupied(entry);
+            return Key::new(self.owner, idx, slot.generation);
         }
         // push a new slot
-        self.entries.push(Entry::Occupied(entry));
+        let generation = self.fresh_generation;
+        self.entries.push(Slot {
+            generation,
+            entry: Entry::Occupied(entry),
+        });
         //Get the new length
         let index = self.entries.len();
 
         // Safety: Vector length is guaranteed to be > 0 here
-        unsafe { NonZeroU32::new_unchecked(index as u32) }
+        Key::new(
+            self.owner,
+            unsafe { NonZeroU32::new_unchecked(index as u32) },
+            generation,
+        )
+    }
+
+    /// Takes the free slot the next allocation goes into, per the strategy
+    fn pop_free(&mut self) -> Option<NonZeroU32> {
+        match self.strategy {
+            SlabStrategy::Lifo => {
+                let idx = self.next_free?;
+                match self.entries[(idx.get() - 1) as usize].entry {
+                    Entry::Free(next_idx) => self.next_free = next_idx,
+                    _ => panic!("Corrupted free list"),
+                }
+                Some(idx)
+            }
+            SlabStrategy::LowestIndex => self.lowest_free.pop().map(|Reverse(idx)| idx),
+        }
+    }
+
+    fn peek_free(&self) -> Option<NonZeroU32> {
+        match self.strategy {
+            SlabStrategy::Lifo => self.next_free,
+            SlabStrategy::LowestIndex => self.lowest_free.peek().map(|&Reverse(idx)| idx),
+        }
+    }
+
+    /// Recollects the free slots after a bulk rewrite of the free list. The list is
+    /// already lowest index first; `LowestIndex` moves it into the heap instead.
+    fn rebuild_free_heap(&mut self) {
+        if self.strategy == SlabStrategy::LowestIndex {
+            let mut free = Vec::with_capacity(self.entries.len() - self.len);
+            for (i, slot) in self.entries.iter_mut().enumerate() {
+                if let Entry::Free(link) = &mut slot.entry {
+                    *link = None;
+                    free.extend(NonZeroU32::new(i as u32 + 1).map(Reverse));
+                }
+            }
+            self.lowest_free = BinaryHeap::from(free);
+            self.next_free = None;
+        }
+    }
+
+    /// The key the next `alloc` will return, so a task can be built knowing its own handle
+    pub fn vacant_key(&self) -> Key {
+        match self.peek_free() {
+            Some(idx) => Key::new(
+                self.owner,
+                idx,
+                self.entries[(idx.get() - 1) as usize].generation,
+            ),
+            None => {
+                let index = u32::try_from(self.entries.len() + 1).expect("slab is full");
+                Key::new(
+                    self.owner,
+                    NonZeroU32::new(index).unwrap(),
+                    self.fresh_generation,
+                )
+            }
+        }
+    }
+
+    /// Frees the entry behind `key`, returning its task.
+    /// Returns `None` for free slots and for keys from an older generation.
+    pub fn free(&mut self, key: Key) -> Option<T> {
+        self.get(key)?;
+        self.free_at(key.index)
     }
 
-    /// Takes a handle (1-based),converts to 0-based index, and frees the entry
-    pub fn free(&mut self, handle: NonZeroU32) -> Option<T> {
-        let idx = (handle.get() - 1) as usize;
+    /// Frees whatever occupies slot `index`, ignoring generations.
+    /// For callers that reached the slot through the intrusive links.
+    pub(crate) fn free_at(&mut self, index: NonZeroU32) -> Option<T> {
+        let idx = (index.get() - 1) as usize;
+        let slot = self.entries.get_mut(idx)?; // Invalid handle
 
-        if idx >= self.entries.len() {
-            return None; // Invalid handle
+        if let Entry::Free(_) = slot.entry {
+            return None; // Already free
         }
 
         // 1. Swap the data out (move it to return it)
-        // 2. Replace it with Entry::Free(old_head)
+        // 2. Replace it with Entry::Free(old_head) and bump the generation
         // 3. Update head to point to this index
-        let new_state = Entry::Free(self.next_free);
-        let old_state = std::mem::replace(&mut self.entries[idx], new_state);
+        let link = match self.strategy {
+            SlabStrategy::Lifo => self.next_free,
+            SlabStrategy::LowestIndex => None,
+        };
+        let old_state = core::mem::replace(&mut slot.entry, Entry::Free(link));
+        slot.generation = slot.generation.wrapping_add(1);
+        match self.strategy {
+            SlabStrategy::Lifo => self.next_free = Some(index), // Now the head of free list
+            SlabStrategy::LowestIndex => self.lowest_free.push(Reverse(index)),
+        }
+        self.len -= 1;
 
         match old_state {
-            Entry::Occupied(entry) => {
-                self.next_free = Some(handle); // This slot is now the head of free list
-                Some(entry.task)
-            }
-            Entry::Free(_) => {
-                // It was already free! Restore the state or panic.
+            Entry::Occupied(entry) => Some(entry.task),
+            Entry::Free(_) => unreachable!(),
+        }
+    }
 
-                self.entries[idx] = old_state;
+    /// Frees every occupied slot, handing each `(deadline, task)` to `on_entry`.
+    /// Generations are bumped so no outstanding key survives, and the free list is rebuilt
+    /// in ascending order so the next allocations start again from index 1.
+    fn reset(&mut self, mut on_entry: impl FnMut(u64, T)) {
+        let len = self.entries.len();
+        for (i, slot) in self.entries.iter_mut().enumerate() {
+            let next = if i + 1 < len {
+                NonZeroU32::new(i as u32 + 2)
+            } else {
                 None
+            };
+            if let Entry::Occupied(entry) = core::mem::replace(&mut slot.entry, Entry::Free(next)) {
+                slot.generation = slot.generation.wrapping_add(1);
+                on_entry(entry.deadline, entry.task);
+            }
+        }
+        self.next_free = if len > 0 { NonZeroU32::new(1) } else { None };
+        self.len = 0;
+        self.rebuild_free_heap();
+    }
+
+    /// Number of occupied slots
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of slots the storage can hold without reallocating
+    pub fn capacity(&self) -> usize {
+        self.entries.capacity()
+    }
+
+    /// Bytes allocated for the entry storage and the free-slot heap, by capacity.
+    /// Unlike `heap_bytes` this is always available, and it also counts the free-slot heap.
+    pub fn memory_bytes(&self) -> usize {
+        self.entries.capacity() * size_of::<Slot<T>>()
+            + self.lowest_free.capacity() * size_of::<Reverse<NonZeroU32>>()
+    }
+
+    /// Makes room for at least `additional` more entries, counting free slots first
+    pub fn reserve(&mut self, additional: usize) {
+        let free = self.entries.len() - self.len;
+        self.entries.reserve(additional.saturating_sub(free));
+    }
+
+    /// Releases free slots past the last occupied one and shrinks the storage towards
+    /// `min_capacity`. Occupied slots never move, so every live key stays valid.
+    /// Returns `true` if the capacity went down.
+    pub fn shrink_to(&mut self, min_capacity: usize) -> bool {
+        let before = self.entries.capacity();
+        let keep = self
+            .entries
+            .iter()
+            .rposition(|slot| matches!(slot.entry, Entry::Occupied(_)))
+            .map_or(0, |last| last + 1);
+
+        for slot in &self.entries[keep..] {
+            self.fresh_generation = self.fresh_generation.max(slot.generation);
+        }
+        self.entries.truncate(keep);
+
+        // Rebuild the free list over what is left, lowest index first
+        let mut next = None;
+        for (i, slot) in self.entries.iter_mut().enumerate().rev() {
+            if let Entry::Free(link) = &mut slot.entry {
+                *link = next;
+                next = NonZeroU32::new(i as u32 + 1);
             }
         }
+        self.next_free = next;
+        self.rebuild_free_heap();
+
+        self.entries.shrink_to(min_capacity);
+        self.entries.capacity() < before
+    }
+
+    /// `shrink_to(0)`: releases every free slot past the last occupied one
+    pub fn shrink_to_fit(&mut self) -> bool {
+        self.shrink_to(0)
+    }
+
+    /// Drops every entry and resets the free list, keeping the allocated capacity
+    pub fn clear(&mut self) {
+        self.reset(|_, _| {});
+    }
+
+    /// Removes every occupied entry, returning `(deadline, task)` pairs in slot order.
+    /// The allocated capacity is kept.
+    pub fn drain(&mut self) -> alloc::vec::IntoIter<(u64, T)> {
+        let mut drained = Vec::new();
+        self.reset(|deadline, task| drained.push((deadline, task)));
+        drained.into_iter()
     }
 
-    pub fn get(&self, handle: NonZeroU32) -> Option<&TimerEntry<T>> {
-        let idx = (handle.get() - 1) as usize;
-        match self.entries.get(idx) {
-            Some(Entry::Occupied(entry)) => Some(entry),
+    pub fn get(&self, key: Key) -> Option<&TimerEntry<T>> {
+        match self.entries.get((key.index.get() - 1) as usize) {
+            Some(Slot {
+                generation,
+                entry: Entry::Occupied(entry),
+            }) if *generation == key.generation && key.owner == self.owner => Some(entry),
             _ => None,
         }
     }
 
-    pub fn get_mut(&mut self, handle: NonZeroU32) -> Option<&mut TimerEntry<T>> {
-        let idx = (handle.get() - 1) as usize;
-        match self.entries.get_mut(idx) {
-            Some(Entry::Occupied(entry)) => Some(entry),
+    pub fn get_mut(&mut self, key: Key) -> Option<&mut TimerEntry<T>> {
+        match self.entries.get_mut((key.index.get() - 1) as usize) {
+            Some(Slot {
+                generation,
+                entry: Entry::Occupied(entry),
+            }) if *generation == key.generation && key.owner == self.owner => Some(entry),
             _ => None,
         }
     }
-    // Helper to get data without references (for tick loop)
-    pub fn remove_and_get_data(&mut self, handle: NonZeroU32) -> Option<(T, u64)> {
-        let idx = (handle.get() - 1) as usize;
 
-        if idx >= self.entries.len() {
-            return None; // Invalid handle
+    /// Whether `key` was handed out by this slab for an entry that has since been freed,
+    /// as opposed to never having named an entry at all. For slots released by a shrink
+    /// this is approximate: any generation below the ones in use counts as released.
+    pub fn was_released(&self, key: Key) -> bool {
+        if key.owner != self.owner {
+            return false;
+        }
+        match self.entries.get((key.index.get() - 1) as usize) {
+            Some(slot) => key.generation < slot.generation,
+            None => key.generation < self.fresh_generation,
+        }
+    }
+
+    /// Every occupied entry with its current key, in slot order
+    pub fn iter(&self) -> impl Iterator<Item = (Key, &TimerEntry<T>)> + '_ {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, slot)| match &slot.entry {
+                Entry::Occupied(entry) => {
+                    // Slot indices are 1-based
+                    let index = NonZeroU32::new(i as u32 + 1)?;
+                    Some((Key::new(self.owner, index, slot.generation), entry))
+                }
+                Entry::Free(_) => None,
+            })
+    }
+
+    /// `iter` with mutable entries
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Key, &mut TimerEntry<T>)> + '_ {
+        let owner = self.owner;
+        self.entries
+            .iter_mut()
+            .enumerate()
+            .filter_map(move |(i, slot)| match &mut slot.entry {
+                Entry::Occupied(entry) => {
+                    let index = NonZeroU32::new(i as u32 + 1)?;
+                    Some((Key::new(owner, index, slot.generation), entry))
+                }
+                Entry::Free(_) => None,
+            })
+    }
+
+    /// The current key of an occupied slot
+    pub fn key_at(&self, index: NonZeroU32) -> Option<Key> {
+        match self.entries.get((index.get() - 1) as usize) {
+            Some(Slot {
+                generation,
+                entry: Entry::Occupied(_),
+            }) => Some(Key::new(self.owner, index, *generation)),
+            _ => None,
+        }
+    }
+
+    /// Entry at slot `index` regardless of generation, for walking the intrusive links
+    pub(crate) fn entry(&self, index: NonZeroU32) -> Option<&TimerEntry<T>> {
+        match self.entries.get((index.get() - 1) as usize) {
+            Some(Slot {
+                entry: Entry::Occupied(entry),
+                ..
+            }) => Some(entry),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn entry_mut(&mut self, index: NonZeroU32) -> Option<&mut TimerEntry<T>> {
+        match self.entries.get_mut((index.get() - 1) as usize) {
+            Some(Slot {
+                entry: Entry::Occupied(entry),
+                ..
+            }) => Some(entry),
+            _ => None,
         }
+    }
 
+    // Helper to get data without references (for tick loop)
+    pub fn remove_and_get_data(&mut self, key: Key) -> Option<(T, u64)> {
         // check if occupied first
-        let deadline = match &self.entries[idx] {
-            Entry::Occupied(e) => e.deadline,
-            _ => return None,
-        };
-        let task = self.free(handle)?;
+        let deadline = self.get(key)?.deadline;
+        let task = self.free(key)?;
         Some((task, deadline))
     }
 }
@@ -136,8 +589,8 @@ mod tests {
         let id_a = slab.alloc("Task A", 100, 0);
         let id_b = slab.alloc("Task B", 200, 0);
 
-        assert_eq!(id_a.get(), 1); // First item should be index 1
-        assert_eq!(id_b.get(), 2); // Second item should be index 2
+        assert_eq!(id_a.index().get(), 1); // First item should be index 1
+        assert_eq!(id_b.index().get(), 2); // Second item should be index 2
 
         // Verify data integrity
         let entry_a = slab.get(id_a).unwrap();
@@ -150,9 +603,9 @@ mod tests {
         // This is the CRITICAL test
         let mut slab = Slab::new();
 
-        let id_1 = slab.alloc(1, 10, 0); // Index 1
+        let _id_1 = slab.alloc(1, 10, 0); // Index 1
         let id_2 = slab.alloc(2, 10, 0); // Index 2
-        let id_3 = slab.alloc(3, 10, 0); // Index 3
+        let _id_3 = slab.alloc(3, 10, 0); // Index 3
 
         // Free the middle one (Index 1)
         let freed_val = slab.free(id_2);
@@ -161,11 +614,11 @@ mod tests {
         // Now allocate a new one. It MUST reuse Index 1.
         let id_4 = slab.alloc(4, 10, 0);
 
-        assert_eq!(id_4.get(), 2, "Slab did not reuse the freed slot!");
+        assert_eq!(id_4.index().get(), 2, "Slab did not reuse the freed slot!");
 
         // Allocate another. Should be Index 4
         let id_5 = slab.alloc(5, 10, 0);
-        assert_eq!(id_5.get(), 4);
+        assert_eq!(id_5.index().get(), 4);
     }
 
     #[test]
@@ -201,11 +654,213 @@ mod tests {
 
         // 3. Allocate 50 new items.
         // These should reuse the freed slots (LIFO order).
+        let mut last = None;
         for i in 0..50 {
-            slab.alloc(i * 100, 0, 0);
+            last = Some(slab.alloc(i * 100, 0, 0));
         }
 
-        let entry = slab.get(handles[0]).unwrap();
+        // The last allocation landed in slot 1 again, under a new generation
+        let last = last.unwrap();
+        assert_eq!(last.index(), handles[0].index());
+        let entry = slab.get(last).unwrap();
         assert_eq!(entry.task, 4900); // 49 * 100
+        assert!(slab.get(handles[0]).is_none());
+    }
+
+    #[cfg(feature = "alloc-tagging")]
+    #[test]
+    fn test_heap_bytes_track_growth() {
+        let mut slab = Slab::new();
+        let entry_size = core::mem::size_of::<Slot<u64>>();
+        assert_eq!(slab.heap_bytes(), 1024 * entry_size);
+
+        for i in 0..1024 {
+            slab.alloc(i, i, 0);
+        }
+        // Still within the preallocated storage
+        assert_eq!(slab.heap_bytes(), 1024 * entry_size);
+
+        slab.alloc(1024, 1024, 0);
+        assert!(slab.heap_bytes() > 1024 * entry_size);
+        assert_eq!(slab.heap_bytes(), slab.entries.capacity() * entry_size);
+
+        // Clearing keeps the storage around for reuse
+        let grown = slab.heap_bytes();
+        slab.clear();
+        assert_eq!(slab.heap_bytes(), grown);
+    }
+
+    #[cfg(feature = "alloc-tagging")]
+    #[test]
+    fn test_heap_bytes_fall_on_shrink_to_fit() {
+        let mut slab = Slab::new();
+        let keys: Vec<Key> = (0..10_000u64).map(|i| slab.alloc(i, i, 0)).collect();
+        let peak = slab.heap_bytes();
+
+        // Draining alone keeps the storage; shrinking gives it back
+        for &key in &keys[1..] {
+            slab.free(key);
+        }
+        assert_eq!(slab.heap_bytes(), peak);
+        assert!(slab.shrink_to_fit());
+        assert!(slab.heap_bytes() < peak);
+        assert_eq!(
+            slab.heap_bytes(),
+            slab.entries.capacity() * core::mem::size_of::<Slot<u64>>()
+        );
+    }
+
+    #[test]
+    fn test_stale_key_does_not_resolve_after_reuse() {
+        let mut slab = Slab::new();
+
+        let old = slab.alloc("old", 10, 0);
+        assert_eq!(slab.free(old), Some("old"));
+
+        // The slot is reused immediately, under a bumped generation
+        let new = slab.alloc("new", 20, 0);
+        assert_eq!(new.index(), old.index());
+        assert_ne!(new.generation(), old.generation());
+
+        assert!(slab.get(old).is_none());
+        assert!(slab.get_mut(old).is_none());
+        assert_eq!(slab.free(old), None);
+        assert_eq!(slab.get(new).unwrap().task, "new");
+    }
+
+    #[test]
+    fn test_clear_invalidates_keys_and_restarts_indices() {
+        let mut slab = Slab::new();
+
+        let a = slab.alloc("a", 1, 0);
+        let b = slab.alloc("b", 2, 0);
+        slab.free(a);
+        slab.clear();
+
+        assert!(slab.get(b).is_none());
+        // Fresh allocations start from the first slot again
+        let c = slab.alloc("c", 3, 0);
+        let d = slab.alloc("d", 4, 0);
+        assert_eq!(c.index().get(), 1);
+        assert_eq!(d.index().get(), 2);
+        assert!(slab.get(a).is_none());
+        assert!(slab.get(b).is_none());
+    }
+
+    #[test]
+    fn test_debug_redacts_tasks() {
+        struct Opaque;
+
+        let mut slab = Slab::new();
+        let key = slab.alloc(Opaque, 42, 1);
+
+        let entry = format!("{:?}", slab.get(key).unwrap());
+        assert!(entry.contains("deadline: 42"));
+        assert!(entry.contains("<redacted"));
+        assert!(entry.contains("Opaque"));
+
+        slab.free(key);
+        assert!(format!("{slab:?}").contains("Free(None)"));
+    }
+
+    #[test]
+    fn test_shrink_keeps_live_keys_and_retires_released_slots() {
+        let mut slab = Slab::new();
+        let keys: Vec<_> = (0..4000u64).map(|i| slab.alloc(i, i, 0)).collect();
+        for &key in &keys[2..] {
+            slab.free(key);
+        }
+        slab.free(keys[0]);
+        assert_eq!(slab.len(), 1);
+
+        let before = slab.capacity();
+        assert!(slab.shrink_to(16));
+        assert!(slab.capacity() < before);
+        assert_eq!(slab.get(keys[1]).map(|e| e.task), Some(1));
+
+        // Freed slot 1 is reused first, then fresh slots past the old tail
+        assert_eq!(slab.alloc(10, 0, 0).index().get(), 1);
+        let fresh = slab.alloc(11, 0, 0);
+        assert_eq!(fresh.index(), keys[2].index());
+        assert!(
+            slab.get(keys[2]).is_none(),
+            "released slot must not resurrect old keys"
+        );
+        assert_eq!(slab.get(fresh).map(|e| e.task), Some(11));
+    }
+
+    #[test]
+    fn test_shrink_to_fit_after_drain() {
+        let mut slab = Slab::new();
+        for i in 0..10_000u64 {
+            slab.alloc(i, i, 0);
+        }
+        assert_eq!(slab.drain().len(), 10_000);
+        let grown = slab.capacity();
+
+        assert!(slab.shrink_to_fit());
+        assert!(slab.capacity() < grown);
+        assert!(!slab.shrink_to_fit(), "nothing left to release");
+        let key = slab.alloc(7, 7, 0);
+        assert_eq!(slab.get(key).map(|e| e.task), Some(7));
+    }
+
+    #[test]
+    fn test_with_capacity_preallocates() {
+        let mut slab = Slab::with_capacity(5_000);
+        let capacity = slab.capacity();
+        assert!(capacity >= 5_000);
+        for i in 0..5_000u64 {
+            slab.alloc(i, i, 0);
+        }
+        assert_eq!(slab.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_reserve_counts_free_slots() {
+        let mut slab = Slab::with_capacity(0);
+        let keys: Vec<_> = (0..100u64).map(|i| slab.alloc(i, i, 0)).collect();
+        for &key in &keys[..40] {
+            slab.free(key);
+        }
+
+        slab.reserve(1_000);
+        let capacity = slab.capacity();
+        assert!(capacity >= 1_060);
+        for i in 0..1_000u64 {
+            slab.alloc(i, i, 0);
+        }
+        assert_eq!(slab.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_lowest_index_reuses_smallest_free_slot() {
+        let mut lifo = Slab::new();
+        let mut lowest = Slab::with_strategy(1024, SlabStrategy::LowestIndex);
+        let lifo_keys: Vec<_> = (0..10u64).map(|i| lifo.alloc(i, i, 0)).collect();
+        let lowest_keys: Vec<_> = (0..10u64).map(|i| lowest.alloc(i, i, 0)).collect();
+        for i in [6, 1, 4] {
+            lifo.free(lifo_keys[i]);
+            lowest.free(lowest_keys[i]);
+        }
+
+        let reused = |slab: &mut Slab<u64>| -> Vec<u32> {
+            (0..4).map(|_| slab.alloc(0, 0, 0).index().get()).collect()
+        };
+        // Most recently freed first, against lowest first; then a fresh slot
+        assert_eq!(reused(&mut lifo), vec![5, 2, 7, 11]);
+        assert_eq!(lowest.vacant_key().index().get(), 2);
+        assert_eq!(reused(&mut lowest), vec![2, 5, 7, 11]);
+        assert!(lowest.get(lowest_keys[1]).is_none());
+
+        // Bulk rewrites of the free list keep the order
+        let keys: Vec<_> = lowest.iter().map(|(key, _)| key).collect();
+        for &key in keys.iter().rev() {
+            lowest.free(key);
+        }
+        assert!(lowest.shrink_to_fit());
+        assert_eq!(lowest.alloc(0, 0, 0).index().get(), 1);
+        lowest.clear();
+        assert_eq!(reused(&mut lowest), vec![1, 2, 3, 4]);
     }
 }
@@ -0,0 +1,192 @@
+use crate::slab::Key;
+use crate::wheel::TimingWheel;
+use std::sync::{Mutex, MutexGuard};
+
+/// A timing wheel that can be shared between threads by reference, e.g. in an `Arc`.
+///
+/// Every call takes one internal lock for its whole duration, so the wheel is never
+/// seen half-updated. No call takes any other lock, and no user code runs while the
+/// lock is held except the tasks' own `Drop`, so it can't take part in a lock-ordering
+/// deadlock. The exceptions are [`with`](Self::with), whose closure runs under the
+/// lock, and any `WheelObserver` installed through it with `set_observer`, whose
+/// callbacks run under the lock during every call that reports to it. Neither may take
+/// other locks or call back into this wheel.
+///
+/// Any thread may insert or cancel, but `tick` should be driven by a single thread.
+/// Ticking from several threads still works, but each call moves time forward, so
+/// the wheel would run as many times too fast.
+pub struct SyncTimingWheel<T> {
+    inner: Mutex<Inner<T>>,
+}
+
+struct Inner<T> {
+    wheel: TimingWheel<T>,
+    // Kept across ticks so a tick only allocates the Vec it returns
+    expired: Vec<T>,
+}
+
+impl<T> Default for SyncTimingWheel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<TimingWheel<T>> for SyncTimingWheel<T> {
+    fn from(wheel: TimingWheel<T>) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                wheel,
+                expired: Vec::new(),
+            }),
+        }
+    }
+}
+
+impl<T> SyncTimingWheel<T> {
+    pub fn new() -> Self {
+        TimingWheel::new().into()
+    }
+
+    pub fn insert(&self, task: T, deadline: u64) -> Key {
+        self.inner().wheel.insert(task, deadline)
+    }
+
+    /// Inserts relative to the wheel's current tick, read under the same lock
+    pub fn insert_in(&self, task: T, delay: u64) -> Key {
+        self.inner().wheel.insert_in(task, delay)
+    }
+
+    pub fn cancel(&self, key: Key) -> Option<T> {
+        self.inner().wheel.cancel(key)
+    }
+
+    /// Advances one tick and returns the tasks that expired on it
+    pub fn tick(&self) -> Vec<T> {
+        let mut inner = self.inner();
+        let Inner { wheel, expired } = &mut *inner;
+        wheel.tick(expired);
+        // `append` leaves the scratch buffer's capacity behind, unlike `mem::take`
+        let mut fired = Vec::with_capacity(expired.len());
+        fired.append(expired);
+        fired
+    }
+
+    pub fn current_time(&self) -> u64 {
+        self.inner().wheel.current_time()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner().wheel.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Runs `f` on the locked wheel, for operations not mirrored here or to do several
+    /// at once without another thread getting in between. An observer set here keeps
+    /// running under the lock after `f` returns.
+    pub fn with<R>(&self, f: impl FnOnce(&mut TimingWheel<T>) -> R) -> R {
+        f(&mut self.inner().wheel)
+    }
+
+    pub fn into_inner(self) -> TimingWheel<T> {
+        self.inner
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .wheel
+    }
+
+    // A panic while the lock was held (in a task's `Drop`, or in a `with` closure)
+    // doesn't leave the wheel's links broken, so poisoning is ignored
+    fn inner(&self) -> MutexGuard<'_, Inner<T>> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[test]
+    fn test_inserts_from_many_threads_tick_from_one() {
+        let wheel = Arc::new(SyncTimingWheel::new());
+        let (cancelled_tx, cancelled_rx) = mpsc::channel();
+
+        let producers: Vec<_> = (0..4u64)
+            .map(|thread| {
+                let wheel = Arc::clone(&wheel);
+                let cancelled_tx = cancelled_tx.clone();
+                thread::spawn(move || {
+                    for i in 0..250 {
+                        let key = wheel.insert((thread, i), 1 + (i * 7 + thread) % 300);
+                        // Every tenth timer is cancelled again by the thread that made it
+                        if i % 10 == 0 {
+                            cancelled_tx.send(wheel.cancel(key).unwrap()).unwrap();
+                        }
+                    }
+                })
+            })
+            .collect();
+        drop(cancelled_tx);
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        assert_eq!(wheel.len(), 900);
+
+        let ticker = {
+            let wheel = Arc::clone(&wheel);
+            thread::spawn(move || {
+                let mut fired = Vec::new();
+                while wheel.current_time() <= 300 {
+                    let tick = wheel.current_time();
+                    for (thread, i) in wheel.tick() {
+                        assert_eq!(1 + (i * 7 + thread) % 300, tick);
+                        fired.push((thread, i));
+                    }
+                }
+                fired
+            })
+        };
+        let mut fired = ticker.join().unwrap();
+        assert!(wheel.is_empty());
+
+        fired.extend(cancelled_rx);
+        fired.sort();
+        let expected: Vec<_> = (0..4u64)
+            .flat_map(|t| (0..250).map(move |i| (t, i)))
+            .collect();
+        assert_eq!(fired, expected);
+    }
+
+    #[test]
+    fn test_tick_reuses_scratch_buffer() {
+        let wheel = SyncTimingWheel::new();
+        for i in 0..100 {
+            wheel.insert(i, 1);
+        }
+        wheel.tick();
+        assert_eq!(wheel.tick(), (0..100).collect::<Vec<_>>());
+        assert!(wheel.inner().expired.capacity() >= 100);
+        assert!(wheel.tick().is_empty());
+    }
+
+    #[test]
+    fn test_with_exposes_the_full_wheel() {
+        let wheel = SyncTimingWheel::new();
+        let key = wheel.insert_in("a", 5);
+        wheel.with(|inner| {
+            let key = inner.reschedule(key, 2).unwrap();
+            assert_eq!(inner.deadline_of(key), Some(2));
+        });
+        wheel.tick();
+        wheel.tick();
+        assert_eq!(wheel.tick(), vec!["a"]);
+        assert_eq!(wheel.into_inner().current_time(), 3);
+    }
+}
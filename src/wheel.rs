@@ -1,125 +1,1098 @@
-use crate::slab::Slab;
-use std::num::NonZeroU32;
-// Constants: Use usize for shifting to avoid constant casting
-const WHEEL_SIZE: usize = 64; // 2^6 slots per wheel
-const WHEEL_BITS: usize = 6;
-const NUM_LEVELS: usize = 4; // Supports up to 64^4 ticks
-const WHEEL_MASK: u64 = 63; // 111111 binary
+use crate::builder::{ShrinkPolicy, WheelBuilder};
+#[cfg(feature = "std")]
+use crate::clock::{Clock, SystemClock};
+use crate::config::WheelConfig;
+use crate::error::{CancelError, ClockWentBackwards, InsertError};
+#[cfg(feature = "debug-events")]
+use crate::events::{EventLog, WheelEvent};
+use crate::observer::WheelObserver;
+use crate::slab::{Key, Slab, TimerEntry};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeSet, BinaryHeap};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::num::{NonZeroU32, NonZeroU64};
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+const DEFAULT_RESOLUTION: Duration = Duration::from_millis(1);
+/// Overflow entries moved into the wheels per tick, unless they are urgent
+const DEFAULT_PROMOTION_BUDGET: usize = 64;
+/// Auto-shrink never takes the slab below its initial reservation
+const MIN_SHRINK_CAPACITY: usize = 1024;
+
+/// Longest `wait_next` sleeps on a deadline it can't turn into an `Instant`
+#[cfg(feature = "std")]
+const MAX_WAIT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Lifetime counters of a wheel, returned by `TimingWheel::stats`.
+///
+/// Timers dropped by `retain` or `clear_level` count as cancelled; ones removed by
+/// `drain` or `clear` count as neither expired nor cancelled. The counters survive
+/// `clear`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WheelStats {
+    pub total_inserted: u64,
+    /// Timers handed out by a tick (or by `expire_all`)
+    pub total_expired: u64,
+    pub total_cancelled: u64,
+    /// Timers moved down a level on their way to their deadline; each move counts
+    pub total_cascades: u64,
+    /// Times the auto-shrink policy released memory
+    pub total_shrinks: u64,
+    /// Pending timers when the snapshot was taken, as `len()`
+    pub current_live: usize,
+    /// Bytes of timer storage held when the snapshot was taken, as `heap_bytes()`
+    #[cfg(feature = "alloc-tagging")]
+    pub heap_bytes: usize,
+}
+
+/// What an insert does with a deadline before `current_time()`, set with
+/// `WheelBuilder::fire_policy`. A deadline equal to `current_time()` is not in the past:
+/// it fires on the next tick, on time, under either policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FirePolicy {
+    /// The timer fires on the next tick, however long ago its deadline was
+    #[default]
+    FireNextTick,
+    /// `try_insert` fails with `InsertError::DeadlineInPast` and the other inserts panic.
+    /// For callers that treat a late deadline as a bug. `reschedule` still clamps.
+    Reject,
+}
+
+/// How closely a timer has to fire to its deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum InsertPrecision {
+    /// Fires on exactly its deadline tick, cascading down the levels as it approaches
+    #[default]
+    Exact,
+    /// Fires as soon as the bucket holding it comes up, skipping every cascade. A timer
+    /// that sits on level `n` fires up to `wheel_size^n` ticks early (one slot span of that
+    /// level) but never late; level-0 timers are still exact.
+    Approximate,
+}
 
 pub struct TimingWheel<T> {
     current_tick: u64,
-    // 4 levels, 64 slots. Each slot holds the head Index of Linked List in the slab
-    wheels: [[Option<NonZeroU32>; WHEEL_SIZE]; NUM_LEVELS],
+    config: WheelConfig,
+    // `num_levels` levels of `wheel_size` slots, level by level. Each slot holds the head
+    // Index of Linked List in the slab
+    wheels: Vec<Option<NonZeroU32>>,
+    // Last entry of each slot's list, laid out like `wheels`. Inserts append here, so a
+    // slot fires in insertion order.
+    tails: Vec<Option<NonZeroU32>>,
+    // One bit per slot, set while its list is non-empty, so looking for the next busy
+    // slot skips a whole word of empty slots at a time. `level_words()` words per level.
+    occupied: Vec<u64>,
+    // Timers linked into each level, adjusted wherever a list gains or loses entries
+    level_counts: Vec<usize>,
     slab: Slab<T>,
+    // Timers beyond the horizon, ordered by deadline. Their tasks stay in the slab so handles
+    // keep working; they are moved into the wheels a few per tick once they come in range.
+    overflow: BTreeSet<(u64, NonZeroU32)>,
+    promotion_budget: usize,
+    // Wall-clock length of one tick, and the instant tick 0 corresponds to
+    resolution: Duration,
+    #[cfg(feature = "std")]
+    origin: Instant,
+    observer: Option<Box<dyn WheelObserver>>,
+    shrink_policy: Option<ShrinkPolicy>,
+    fire_policy: FirePolicy,
+    max_timers: Option<usize>,
+    // Totals only; `current_live` is filled in by `stats()`
+    stats: WheelStats,
+    // Filled by `tick_internal`, emptied by `take_expired`; keeps its capacity
+    expired: Vec<T>,
+    // `T::clone`, recorded by the first `insert_periodic` so the expiry path can re-arm
+    // periodic timers without every wheel requiring `T: Clone`
+    clone_task: Option<fn(&T) -> T>,
+    // Set by `pause`: every way of moving time is a no-op until `resume`
+    paused: bool,
+    #[cfg(feature = "debug-events")]
+    events: EventLog,
+}
+
+// Tasks are redacted by the slab, so the wheel is printable whatever `T` is
+impl<T> fmt::Debug for TimingWheel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimingWheel")
+            .field("current_tick", &self.current_tick)
+            .field("pending", &self.slab.len())
+            .field("levels", &Occupancy(&self.dump_occupancy()))
+            .field("config", &self.config)
+            .field("resolution", &self.resolution)
+            .field("overflow", &self.overflow)
+            .field("slab", &self.slab)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Debug view of `dump_occupancy`: per level, only the non-empty slots and their chain lengths
+struct Occupancy<'a>(&'a [Vec<usize>]);
+
+struct LevelOccupancy<'a>(&'a [usize]);
+
+impl fmt::Debug for Occupancy<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.0.iter().map(|level| LevelOccupancy(level)))
+            .finish()
+    }
+}
+
+impl fmt::Debug for LevelOccupancy<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().enumerate().filter(|&(_, &len)| len > 0))
+            .finish()
+    }
+}
+
+impl<T> Default for TimingWheel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Iterator behind `TimingWheel::tick_iter`
+struct TickIter<'a, T> {
+    wheel: &'a mut TimingWheel<T>,
+    slot: usize,
+    // Whether the clock has advanced; after that only `spill` is left to yield
+    finished: bool,
+    // Approximate timers that expired during the cascade
+    spill: alloc::vec::IntoIter<T>,
+}
+
+impl<T> Iterator for TickIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if !self.finished {
+            if let Some(task) = self.wheel.pop_due(self.slot) {
+                return Some(task);
+            }
+            let mut spill = Vec::new();
+            self.wheel.finish_tick(&mut spill);
+            self.finished = true;
+            self.spill = spill.into_iter();
+        }
+        self.spill.next()
+    }
+}
+
+impl<T> Drop for TickIter<'_, T> {
+    fn drop(&mut self) {
+        if !self.finished {
+            while self.wheel.pop_due(self.slot).is_some() {}
+            self.wheel.finish_tick(&mut Vec::new());
+        }
+    }
+}
+
+/// A timer that is cancelled when the guard goes out of scope, returned by
+/// `TimingWheel::insert_guard`.
+///
+/// The guard holds the wheel mutably borrowed; drive the wheel through `wheel()` while
+/// the guard is alive. Call `forget` to let the timer outlive the scope.
+pub struct TimerGuard<'a, T> {
+    wheel: &'a mut TimingWheel<T>,
+    // `None` once forgotten
+    key: Option<Key>,
+}
+
+impl<T> TimerGuard<'_, T> {
+    /// Handle of the guarded timer
+    pub fn id(&self) -> Key {
+        self.key.expect("guard already forgotten")
+    }
+
+    /// Whether the timer is still pending, i.e. has neither fired nor been cancelled
+    pub fn is_armed(&self) -> bool {
+        self.key.is_some_and(|key| self.wheel.contains(key))
+    }
+
+    /// The wheel the timer lives in, e.g. to tick it inside the guarded scope
+    pub fn wheel(&mut self) -> &mut TimingWheel<T> {
+        self.wheel
+    }
+
+    /// Releases the guard without cancelling, returning the timer's handle. The timer
+    /// then fires or is cancelled like any other.
+    pub fn forget(mut self) -> Key {
+        self.key.take().expect("guard already forgotten")
+    }
+}
+
+impl<T> Drop for TimerGuard<'_, T> {
+    fn drop(&mut self) {
+        // A no-op if the timer already fired inside the scope
+        if let Some(key) = self.key {
+            self.wheel.cancel(key);
+        }
+    }
+}
+
+/// Consumes the wheel, yielding `(deadline, task)` for every pending timer, e.g. to move
+/// them into a wheel with a different resolution. Deadlines are the absolute ones the
+/// timers were scheduled with, wherever they currently sit. Order is unspecified.
+impl<T> IntoIterator for TimingWheel<T> {
+    type Item = (u64, T);
+    type IntoIter = crate::slab::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.slab.into_iter()
+    }
+}
+
+/// Bulk-loads `(task, deadline)` pairs, discarding the handles; see `insert_batch`
+impl<T> Extend<(T, u64)> for TimingWheel<T> {
+    fn extend<I: IntoIterator<Item = (T, u64)>>(&mut self, timers: I) {
+        let timers = timers.into_iter();
+        self.slab.reserve(timers.size_hint().0);
+        for (task, deadline) in timers {
+            self.insert(task, deadline);
+        }
+    }
+}
+
+impl<T> FromIterator<(T, u64)> for TimingWheel<T> {
+    fn from_iter<I: IntoIterator<Item = (T, u64)>>(timers: I) -> Self {
+        let mut wheel = Self::new();
+        wheel.extend(timers);
+        wheel
+    }
 }
 
 impl<T> TimingWheel<T> {
     pub fn new() -> Self {
+        Self::new_with_resolution(DEFAULT_RESOLUTION)
+    }
+
+    /// Creates a wheel where one tick lasts `tick`, anchored at `Instant::now()`.
+    /// `new()` uses a 1ms resolution.
+    pub fn new_with_resolution(tick: Duration) -> Self {
+        Self::from_parts(WheelConfig::default(), Slab::with_capacity(1024), tick)
+    }
+
+    /// Creates a wheel that holds `capacity` pending timers before its storage has to grow,
+    /// e.g. to load millions of timers at startup with a single allocation
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_parts(
+            WheelConfig::default(),
+            Slab::with_capacity(capacity),
+            DEFAULT_RESOLUTION,
+        )
+    }
+
+    /// Creates a wheel that refuses to hold more than `max` pending timers: beyond that,
+    /// `try_insert` hands the task back and the other inserts panic. Slots free up again
+    /// as timers fire or are cancelled.
+    pub fn with_max_timers(max: usize) -> Self {
+        let mut wheel = Self::new();
+        wheel.max_timers = Some(max);
+        wheel
+    }
+
+    /// Creates a wheel with a custom level layout; see `WheelConfig`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is out of range.
+    pub fn with_config(config: WheelConfig) -> Self {
+        Self::from_parts(config, Slab::with_capacity(1024), DEFAULT_RESOLUTION)
+    }
+
+    fn from_parts(config: WheelConfig, slab: Slab<T>, tick: Duration) -> Self {
+        assert!(!tick.is_zero(), "tick resolution must be non-zero");
+        config.validate();
+
         Self {
             current_tick: 0,
-            wheels: [[None; WHEEL_SIZE]; NUM_LEVELS],
-            slab: Slab::new(),
+            config,
+            wheels: vec![None; config.num_levels as usize * config.wheel_size()],
+            tails: vec![None; config.num_levels as usize * config.wheel_size()],
+            occupied: vec![0; config.num_levels as usize * config.wheel_size().div_ceil(64)],
+            level_counts: vec![0; config.num_levels as usize],
+            slab,
+            overflow: BTreeSet::new(),
+            promotion_budget: DEFAULT_PROMOTION_BUDGET,
+            resolution: tick,
+            #[cfg(feature = "std")]
+            origin: Instant::now(),
+            observer: None,
+            shrink_policy: None,
+            fire_policy: FirePolicy::default(),
+            max_timers: None,
+            stats: WheelStats::default(),
+            expired: Vec::new(),
+            clone_task: None,
+            paused: false,
+            #[cfg(feature = "debug-events")]
+            events: EventLog::new(),
+        }
+    }
+
+    /// A new wheel holding every `(task, deadline)` pair, e.g. to restore a snapshot.
+    /// Slab space is reserved once from the iterator's size hint. Same as `collect()`.
+    pub fn from_pairs<I: IntoIterator<Item = (T, u64)>>(timers: I) -> Self {
+        timers.into_iter().collect()
+    }
+
+    /// Starts configuring a wheel; see `WheelBuilder`
+    pub fn builder() -> WheelBuilder<T> {
+        WheelBuilder::new()
+    }
+
+    pub(crate) fn from_builder(builder: &WheelBuilder<T>) -> Self {
+        let slab = Slab::with_strategy(builder.capacity, builder.slab_strategy);
+        let mut wheel = Self::from_parts(builder.config, slab, builder.resolution);
+        wheel.shrink_policy = builder.shrink;
+        wheel.fire_policy = builder.fire_policy;
+        wheel.max_timers = builder.max_timers;
+        wheel
+    }
+
+    /// Schedules `task` to fire on the absolute tick `deadline`; `insert_in` takes a
+    /// delay relative to `current_time()` instead. A deadline that already passed fires
+    /// on the next tick, unless the wheel was built with `FirePolicy::Reject`.
+    ///
+    /// Timers sharing a deadline fire in the order they were inserted, whichever levels
+    /// they passed through; `reschedule` counts as a fresh insert. Approximate timers and
+    /// timers parked beyond the horizon are the exception and may be reordered among
+    /// themselves; use `tick_sorted` where a batch must be in deadline order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `deadline` is in the past and the fire policy is `FirePolicy::Reject`,
+    /// or if the wheel already holds its maximum number of timers.
+    pub fn insert(&mut self, task: T, deadline: u64) -> Key {
+        self.insert_entry(task, deadline, 0)
+    }
+
+    /// `insert` with a delivery priority. Timers firing on the same tick are delivered in
+    /// descending priority, and in the usual insertion order within a priority; plain
+    /// `insert` uses priority 0.
+    ///
+    /// Priorities order the timers expiring from the current level-0 slot: exact timers,
+    /// whether inserted there or cascaded down from an upper level, and overdue ones.
+    /// Approximate timers expiring in the cascade at the end of the tick come after
+    /// them, in cascade order.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `insert`.
+    pub fn insert_with_priority(&mut self, task: T, deadline: u64, priority: u8) -> Key {
+        self.insert_entry(task, deadline, priority)
+    }
+
+    fn insert_entry(&mut self, task: T, deadline: u64, priority: u8) -> Key {
+        self.assert_insertable(deadline);
+        // Allocate in the slab, then hook the entry into its slot
+        let key = self.slab.alloc(task, deadline, 0);
+        if priority != 0
+            && let Some(entry) = self.slab.entry_mut(key.index())
+        {
+            entry.priority = priority;
+        }
+        let _level = self.link(key.index());
+        self.stats.total_inserted += 1;
+
+        #[cfg(feature = "debug-events")]
+        self.events.record(WheelEvent::Insert {
+            handle: key,
+            deadline,
+            level: _level,
+        });
+
+        key
+    }
+
+    /// `insert` for a task that needs its own handle, e.g. a connection that cancels its
+    /// idle timer itself. `make_task` is called with the handle the timer will have, and
+    /// nothing is allocated until it returns.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `insert`.
+    pub fn insert_with<F: FnOnce(Key) -> T>(&mut self, deadline: u64, make_task: F) -> Key {
+        let key = self.vacant_key();
+        let task = make_task(key);
+        let inserted = self.insert(task, deadline);
+        debug_assert_eq!(inserted, key, "slab handed out a different slot");
+        inserted
+    }
+
+    /// `insert` that never panics: a timer the wheel has no room for, or a deadline in
+    /// the past under `FirePolicy::Reject`, is refused and the task handed back.
+    pub fn try_insert(&mut self, task: T, deadline: u64) -> Result<Key, InsertError<T>> {
+        if self.is_full() {
+            return Err(InsertError::Full(task));
+        }
+        if self.rejects(deadline) {
+            return Err(InsertError::DeadlineInPast(task));
+        }
+        Ok(self.insert(task, deadline))
+    }
+
+    /// The handle the next insert will be given
+    pub(crate) fn vacant_key(&self) -> Key {
+        self.slab.vacant_key()
+    }
+
+    /// Whether the limit set by `with_max_timers` is reached
+    pub fn is_full(&self) -> bool {
+        self.max_timers.is_some_and(|max| self.len() >= max)
+    }
+
+    fn rejects(&self, deadline: u64) -> bool {
+        self.fire_policy == FirePolicy::Reject && deadline < self.current_tick
+    }
+
+    fn assert_insertable(&self, deadline: u64) {
+        assert!(!self.is_full(), "wheel is full");
+        assert!(
+            !self.rejects(deadline),
+            "deadline {deadline} is before the current tick {}",
+            self.current_tick
+        );
+    }
+
+    /// Fire-and-forget `insert` for timers that are never cancelled or rescheduled, such
+    /// as one-shot timeouts that almost always fire. No handle is returned; the timer is
+    /// stored exactly like one from `insert`, so this is only a convenience for callers
+    /// with no use for the handle, not a cheaper insert.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `insert`.
+    pub fn insert_oneshot(&mut self, task: T, deadline: u64) {
+        self.insert(task, deadline);
+    }
+
+    /// `insert` for a timer scoped to the returned guard: dropping the guard cancels the
+    /// timer unless `TimerGuard::forget` was called. Handy for timeouts around a block of
+    /// work that must not fire once the block is left.
+    pub fn insert_guard(&mut self, task: T, deadline: u64) -> TimerGuard<'_, T> {
+        let key = self.insert(task, deadline);
+        TimerGuard {
+            wheel: self,
+            key: Some(key),
+        }
+    }
+
+    /// Schedules `task` to fire `ticks_from_now` ticks after `current_time()`
+    pub fn insert_in(&mut self, task: T, ticks_from_now: u64) -> Key {
+        let deadline = self.current_tick.saturating_add(ticks_from_now);
+        self.insert(task, deadline)
+    }
+
+    /// `insert` with a chosen precision; `insert` is `InsertPrecision::Exact`
+    pub fn insert_with_precision(
+        &mut self,
+        task: T,
+        deadline: u64,
+        precision: InsertPrecision,
+    ) -> Key {
+        let key = self.insert(task, deadline);
+        if let Some(entry) = self.slab.entry_mut(key.index()) {
+            entry.approximate = precision == InsertPrecision::Approximate;
+        }
+        key
+    }
+
+    /// `insert`, tagging the timer with `group` so it can be cancelled with the rest of
+    /// its group by `cancel_group`, e.g. every timer of one session
+    pub fn insert_grouped(&mut self, task: T, deadline: u64, group: u32) -> Key {
+        let key = self.insert(task, deadline);
+        if let Some(entry) = self.slab.entry_mut(key.index()) {
+            entry.group = Some(group);
+        }
+        key
+    }
+
+    /// Inserts a timer that fires at `first_deadline` and then every `interval` ticks
+    /// after that, each time handing out a clone of `task`, until it is cancelled. The
+    /// handle stays valid across firings, including for `reschedule`, which moves the
+    /// next firing and continues the period from there.
+    ///
+    /// Each firing arms the next one at the previous deadline plus `interval`. If that
+    /// has already passed, e.g. because `first_deadline` was, it fires on the next tick
+    /// instead: missed periods are skipped, not fired in a burst. Firing on the last tick,
+    /// `u64::MAX`, ends the timer, since the clock cannot move past it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is 0, or under the same conditions as `insert`.
+    pub fn insert_periodic(&mut self, task: T, first_deadline: u64, interval: u64) -> Key
+    where
+        T: Clone,
+    {
+        let interval = NonZeroU64::new(interval).expect("interval must be at least one tick");
+        self.clone_task = Some(T::clone);
+        let key = self.insert(task, first_deadline);
+        if let Some(entry) = self.slab.entry_mut(key.index()) {
+            entry.interval = Some(interval);
+        }
+        key
+    }
+
+    /// Cancels every pending timer inserted into `group`, returning their tasks in
+    /// unspecified order. Timers of the group that already fired or were cancelled are
+    /// simply gone. Scans the whole slab, so it runs in O(capacity).
+    pub fn cancel_group(&mut self, group: u32) -> Vec<T> {
+        let members: Vec<Key> = self
+            .slab
+            .iter()
+            .filter(|(_, entry)| entry.group == Some(group))
+            .map(|(key, _)| key)
+            .collect();
+        members
+            .into_iter()
+            .filter_map(|key| self.cancel(key))
+            .collect()
+    }
+
+    /// Every pending timer's entry, for snapshots that keep more than `iter` shows
+    #[cfg(feature = "serde")]
+    pub(crate) fn entries(&self) -> impl Iterator<Item = &TimerEntry<T>> + '_ {
+        self.slab.iter().map(|(_, entry)| entry)
+    }
+
+    /// Lets periodic timers restored from a snapshot hand out clones of their task
+    #[cfg(feature = "serde")]
+    pub(crate) fn enable_periodic(&mut self)
+    where
+        T: Clone,
+    {
+        self.clone_task = Some(T::clone);
+    }
+
+    /// Re-inserts a snapshotted timer with the attributes it was originally inserted with.
+    /// A periodic one only re-arms once `enable_periodic` was called.
+    #[cfg(feature = "serde")]
+    pub(crate) fn restore(
+        &mut self,
+        task: T,
+        deadline: u64,
+        priority: u8,
+        group: Option<u32>,
+        interval: Option<NonZeroU64>,
+        approximate: bool,
+    ) -> Key {
+        let key = self.insert_entry(task, deadline, priority);
+        if let Some(entry) = self.slab.entry_mut(key.index()) {
+            entry.group = group;
+            entry.interval = interval;
+            entry.approximate = approximate;
+        }
+        key
+    }
+
+    /// Inserts every `(task, deadline)` pair, returning their handles in order.
+    /// Slab space is reserved up front from the iterator's size hint.
+    pub fn insert_batch<I>(&mut self, timers: I) -> Vec<Key>
+    where
+        I: IntoIterator<Item = (T, u64)>,
+    {
+        let timers = timers.into_iter();
+        self.slab.reserve(timers.size_hint().0);
+        timers
+            .map(|(task, deadline)| self.insert(task, deadline))
+            .collect()
+    }
+
+    /// Schedules `task` to fire `delay` after the current tick.
+    /// The delay is rounded up to whole ticks, so a timer never fires early.
+    pub fn insert_after(&mut self, task: T, delay: Duration) -> Key {
+        let deadline = self
+            .current_tick
+            .saturating_add(self.duration_to_ticks(delay));
+        self.insert(task, deadline)
+    }
+
+    /// Schedules `task` to fire at the wall-clock instant `when`, rounded up to the next tick.
+    /// Instants before the wheel's origin map to tick 0.
+    #[cfg(feature = "std")]
+    pub fn insert_at(&mut self, task: T, when: Instant) -> Key {
+        let since_origin = when.saturating_duration_since(self.origin);
+        let deadline = self.duration_to_ticks(since_origin);
+        self.insert(task, deadline)
+    }
+
+    /// Every pending timer as `(handle, task, deadline)`, across all levels and the
+    /// overflow set. Order is unspecified.
+    pub fn iter(&self) -> impl Iterator<Item = (Key, &T, u64)> + '_ {
+        self.slab
+            .iter()
+            .map(|(key, entry)| (key, &entry.task, entry.deadline))
+    }
+
+    /// Up to `n` pending timers with the earliest deadlines, as `(deadline, task)` sorted
+    /// by deadline, without firing anything. Timers sharing a deadline come in no
+    /// particular order.
+    ///
+    /// Meant for monitoring: it walks every pending timer, in every level and the
+    /// overflow set, so it costs O(len · log n).
+    pub fn peek_soonest(&self, n: usize) -> Vec<(u64, &T)> {
+        if n == 0 {
+            return Vec::new();
+        }
+        // Max-heap of the best `n` so far; the root is the first to be displaced
+        let mut soonest = BinaryHeap::with_capacity(n + 1);
+        for (key, entry) in self.slab.iter() {
+            if soonest.len() < n {
+                soonest.push((entry.deadline, key));
+            } else if let Some(&(latest, _)) = soonest.peek()
+                && entry.deadline < latest
+            {
+                soonest.pop();
+                soonest.push((entry.deadline, key));
+            }
         }
+        soonest
+            .into_sorted_vec()
+            .into_iter()
+            .filter_map(|(deadline, key)| Some((deadline, self.get(key)?)))
+            .collect()
+    }
+
+    /// `iter` with mutable access to the tasks. Deadlines are read-only: use `reschedule`
+    /// to move a timer.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Key, &mut T, u64)> + '_ {
+        self.slab
+            .iter_mut()
+            .map(|(key, entry)| (key, &mut entry.task, entry.deadline))
+    }
+
+    /// Converts a duration into a number of ticks, rounding up
+    fn duration_to_ticks(&self, duration: Duration) -> u64 {
+        let resolution = self.resolution.as_nanos();
+        let ticks = duration.as_nanos().div_ceil(resolution);
+        u64::try_from(ticks).unwrap_or(u64::MAX)
+    }
+
+    /// Links a detached slab entry at the tail of the slot matching its deadline.
+    /// Used for fresh inserts and reschedules, so a slot holds them in insertion order.
+    fn link(&mut self, idx: NonZeroU32) -> u8 {
+        self.link_at(idx, false)
+    }
+
+    /// Links an entry that moves down from an upper level or the overflow set at the
+    /// head of its new slot. It was inserted before anything that went straight into
+    /// that slot with the same deadline (it was further out when inserted), so this
+    /// keeps timers sharing a deadline in insertion order. The entry keeps its handle.
+    fn link_cascaded(&mut self, idx: NonZeroU32) -> u8 {
+        self.link_at(idx, true)
     }
 
-    pub fn insert(&mut self, task: T, deadline: u64) -> NonZeroU32 {
-        let duration = deadline.saturating_sub(self.current_tick);
+    fn link_at(&mut self, idx: NonZeroU32, front: bool) -> u8 {
+        let deadline = self
+            .slab
+            .entry(idx)
+            .expect("linking a free slab entry")
+            .deadline;
+        let (level, slot) = self.config.placement(self.current_tick, deadline);
+
+        // Too far out for the wheels: park it until it comes in range
+        if level == self.config.overflow_level() {
+            if let Some(entry) = self.slab.entry_mut(idx) {
+                entry.next = None;
+                entry.prev = None;
+                entry.level = level;
+                entry.slot = 0;
+            }
+            self.overflow.insert((deadline, idx));
+            if let Some(observer) = self.observer.as_mut()
+                && let Some(key) = self.slab.key_at(idx)
+            {
+                observer.on_overflow_parked(key);
+            }
+            return level;
+        }
 
-        // 1. Determine which Level (Wheel) this belongs to
-        let level = if duration < (1 << WHEEL_BITS) {
-            0
-        } else if duration < (1 << (2 * WHEEL_BITS)) {
-            1
-        } else if duration < (1 << (3 * WHEEL_BITS)) {
-            2
+        let bucket = self.bucket(level as usize, slot as usize);
+        let (prev, next) = if level == 0 {
+            self.priority_position(bucket, idx, front)
+        } else if front {
+            (None, self.wheels[bucket])
         } else {
-            3
+            (self.tails[bucket], None)
         };
 
-        // 2. Determine Which Slot (Bucket)
-        let shift = level * WHEEL_BITS;
-        let slot = ((deadline >> shift) & WHEEL_MASK) as usize;
+        // Intrusive Linked List Insertion between `prev` and `next`
+        if let Some(entry) = self.slab.entry_mut(idx) {
+            entry.next = next;
+            entry.prev = prev;
+            entry.level = level;
+            entry.slot = slot;
+        }
+        match prev {
+            Some(prev_idx) => {
+                if let Some(prev_entry) = self.slab.entry_mut(prev_idx) {
+                    prev_entry.next = Some(idx);
+                }
+            }
+            None => self.wheels[bucket] = Some(idx),
+        }
+        match next {
+            Some(next_idx) => {
+                if let Some(next_entry) = self.slab.entry_mut(next_idx) {
+                    next_entry.prev = Some(idx);
+                }
+            }
+            None => self.tails[bucket] = Some(idx),
+        }
+        self.mark_occupied(level as usize, slot as usize, true);
+        self.level_counts[level as usize] += 1;
+
+        level
+    }
 
-        // 3. Allocate in the slab
-        let new_idx = self.slab.alloc(task, deadline, level as u8);
+    /// Where `idx` goes in the level-0 list `bucket`, as `(prev, next)`. The list is kept
+    /// in descending priority, so a tick delivers higher priorities first. Within a priority
+    /// an entry goes after its peers, or before them with `front`, exactly like the plain
+    /// tail or head link. Without priorities the search stops at the first step.
+    fn priority_position(
+        &self,
+        bucket: usize,
+        idx: NonZeroU32,
+        front: bool,
+    ) -> (Option<NonZeroU32>, Option<NonZeroU32>) {
+        let entry_at = |idx: NonZeroU32| self.slab.entry(idx).expect("bucket links a free slot");
+        let priority = entry_at(idx).priority;
+        if front {
+            let mut next = self.wheels[bucket];
+            let mut prev = None;
+            while let Some(n) = next.filter(|&n| entry_at(n).priority > priority) {
+                prev = Some(n);
+                next = entry_at(n).next;
+            }
+            (prev, next)
+        } else {
+            let mut prev = self.tails[bucket];
+            let mut next = None;
+            while let Some(p) = prev.filter(|&p| entry_at(p).priority < priority) {
+                next = Some(p);
+                prev = entry_at(p).prev;
+            }
+            (prev, next)
+        }
+    }
 
-        // 4. Intrusive Linked List Insertion at the head of the slot
-        let old_head_idx = self.wheels[level][slot];
+    /// Moves a pending timer to `new_deadline` without moving its task out of the slab,
+    /// returning the handle to keep using. Cheaper than `cancel` + `insert`.
+    ///
+    /// The entry stays in its slot, so today the returned handle is always `key` itself;
+    /// callers should still store what comes back. A deadline that is already in the past
+    /// is clamped to the current tick, so the timer fires on the next `tick`; one beyond
+    /// the horizon is parked in the overflow set exactly like `insert` would.
+    /// Returns `None` if `key` is stale.
+    pub fn reschedule(&mut self, key: Key, new_deadline: u64) -> Option<Key> {
+        let old_deadline = self.deadline_of(key)?;
+        let new_deadline = new_deadline.max(self.current_tick);
 
-        // Update the NEW entry's pointers
-        if let Some(mut entry) = self.slab.get_mut(new_idx) {
-            entry.next = old_head_idx;
-            entry.prev = None;
+        let idx = key.index();
+        self.unlink(idx);
+        if let Some(entry) = self.slab.entry_mut(idx) {
+            entry.deadline = new_deadline;
         }
+        self.link(idx);
 
-        // Update the OLD head's prev pointer
-        if let Some(old_idx) = old_head_idx {
-            if let Some(mut old_head) = self.slab.get_mut(old_idx) {
-                old_head.prev = Some(new_idx);
-            }
+        if let Some(observer) = self.observer.as_mut() {
+            observer.on_reschedule(key, old_deadline, new_deadline);
         }
+        Some(key)
+    }
+
+    /// Keep-alive helper: pushes the timer out to `extra` ticks from now, wherever its
+    /// deadline was before. Returns the new absolute deadline, or `None` if `key` is stale.
+    pub fn touch(&mut self, key: Key, extra: u64) -> Option<u64> {
+        let deadline = self.current_tick.saturating_add(extra);
+        self.reschedule(key, deadline).map(|_| deadline)
+    }
+
+    /// Cancels a pending timer, returning its task.
+    /// Returns `None` if the timer already fired or was cancelled, even if its slab
+    /// slot has since been reused by another timer.
+    pub fn cancel(&mut self, key: Key) -> Option<T> {
+        // Validate the handle (including its generation) before touching any links
+        self.slab.get(key)?;
+        self.unlink(key.index());
+
+        #[cfg(feature = "debug-events")]
+        self.events.record(WheelEvent::Cancel { handle: key });
+
+        // Finally free the memory and return task
+        self.stats.total_cancelled += 1;
+        self.slab.free(key)
+    }
+
+    /// Cancels every timer in `keys`, pushing the recovered tasks into `out` in key order,
+    /// e.g. to tear down a failed-over shard's timers in one call. Stale and repeated keys
+    /// are skipped. Returns how many timers were cancelled.
+    pub fn cancel_batch(&mut self, keys: &[Key], out: &mut Vec<T>) -> usize {
+        let before = out.len();
+        out.extend(keys.iter().filter_map(|&key| self.cancel(key)));
+        out.len() - before
+    }
+
+    /// `cancel`, telling apart the two ways it can fail: the timer already fired or was
+    /// cancelled (a normal race with expiry), or the handle never named a timer of this
+    /// wheel (a bug worth logging loudly).
+    pub fn try_cancel(&mut self, key: Key) -> Result<T, CancelError> {
+        self.cancel(key).ok_or(if self.slab.was_released(key) {
+            CancelError::AlreadyCompleted
+        } else {
+            CancelError::InvalidHandle
+        })
+    }
 
-        // Update the wheel bucket to point to the new entry
-        self.wheels[level][slot] = Some(new_idx);
+    /// Whether the timer behind `key` is still armed.
+    /// Generations make this exact: handles of timers that fired or were cancelled report
+    /// false even after their slab slot is reused.
+    pub fn contains(&self, key: Key) -> bool {
+        self.slab.get(key).is_some()
+    }
+
+    /// Absolute tick at which the timer behind `key` will fire, if it is still armed
+    pub fn deadline_of(&self, key: Key) -> Option<u64> {
+        self.slab.get(key).map(|entry| entry.deadline)
+    }
+
+    /// The task of a pending timer, or `None` if it already fired or was cancelled
+    pub fn get(&self, key: Key) -> Option<&T> {
+        self.slab.get(key).map(|entry| &entry.task)
+    }
 
-        new_idx
+    /// Mutable access to the task of a pending timer, e.g. to update state attached to
+    /// an in-flight request while its timeout is still armed
+    pub fn get_mut(&mut self, key: Key) -> Option<&mut T> {
+        self.slab.get_mut(key).map(|entry| &mut entry.task)
     }
 
-    pub fn cancel(&mut self, idx: NonZeroU32) -> Option<T> {
+    /// Detaches a live entry from whichever slot (or the overflow set) it is linked into.
+    /// The entry itself stays allocated.
+    fn unlink(&mut self, idx: NonZeroU32) {
         // 1. Read metadata to find where this entry lives
-        let (prev, next, deadline, level) = {
-            let entry = self.slab.get(idx)?;
-            (entry.prev, entry.next, entry.deadline, entry.level)
+        let Some(entry) = self.slab.entry(idx) else {
+            return;
         };
+        let (prev, next, deadline, level, slot) = (
+            entry.prev,
+            entry.next,
+            entry.deadline,
+            entry.level,
+            entry.slot as usize,
+        );
+
+        if level == self.config.overflow_level() {
+            self.overflow.remove(&(deadline, idx));
+            return;
+        }
 
-        // re-calculate slot again just to update the wheel head if needed
-        let shift = (level as usize) * WHEEL_BITS;
-        let slot = ((deadline >> shift) & WHEEL_MASK) as usize;
+        let bucket = self.bucket(level as usize, slot);
+        self.level_counts[level as usize] -= 1;
 
         // 2. Unlink from "Prev"
         if let Some(prev_idx) = prev {
-            if let Some(mut prev_entry) = self.slab.get_mut(prev_idx) {
+            if let Some(prev_entry) = self.slab.entry_mut(prev_idx) {
                 prev_entry.next = next;
             }
         } else {
-            self.wheels[level as usize][slot] = next;
+            self.wheels[bucket] = next;
+            if next.is_none() {
+                self.mark_occupied(level as usize, slot, false);
+            }
         }
 
         // 3. Unlink from "Next"
         if let Some(next_idx) = next {
-            if let Some(mut next_entry) = self.slab.get_mut(next_idx) {
+            if let Some(next_entry) = self.slab.entry_mut(next_idx) {
                 next_entry.prev = prev;
             }
+        } else {
+            self.tails[bucket] = prev;
+        }
+    }
+
+    /// Moves overflow timers that now fit within the horizon into the wheels, earliest first.
+    /// At most `promotion_budget` of them move per tick, so a burst of far-future timers that
+    /// all come in range together does not land on a single tick. An entry becomes eligible a
+    /// whole horizon before it is due, which leaves plenty of ticks to drain any backlog; as a
+    /// safety net anything within one top-level slot of its deadline is promoted regardless.
+    fn promote_overflow(&mut self) {
+        let horizon = self.config.horizon();
+        let urgent = self.urgent_promotion();
+        let mut promoted = 0;
+        while let Some(&(deadline, idx)) = self.overflow.first() {
+            let remaining = deadline.saturating_sub(self.current_tick);
+            if remaining >= horizon || (promoted >= self.promotion_budget && remaining >= urgent) {
+                break;
+            }
+            self.overflow.pop_first();
+            self.link_cascaded(idx);
+            promoted += 1;
+        }
+    }
+
+    /// Overflow entries closer than one top-level slot are promoted regardless of the budget
+    fn urgent_promotion(&self) -> u64 {
+        self.config.horizon() >> self.config.wheel_bits
+    }
+
+    /// Index of `(level, slot)` in the flat bucket storage
+    #[inline]
+    fn bucket(&self, level: usize, slot: usize) -> usize {
+        level * self.config.wheel_size() + slot
+    }
+
+    /// Words of the `occupied` bitmap per level
+    #[inline]
+    fn level_words(&self) -> usize {
+        self.config.wheel_size().div_ceil(64)
+    }
+
+    fn mark_occupied(&mut self, level: usize, slot: usize, busy: bool) {
+        let word = level * self.level_words() + slot / 64;
+        let bit = 1u64 << (slot % 64);
+        if busy {
+            self.occupied[word] |= bit;
+        } else {
+            self.occupied[word] &= !bit;
         }
+    }
+
+    /// How many slots past `from` the first non-empty slot of `level` is, going round
+    /// the level once starting with `from` itself
+    fn next_occupied(&self, level: usize, from: usize) -> Option<usize> {
+        let words = self.level_words();
+        let bits = &self.occupied[level * words..(level + 1) * words];
+        // First set bit in `start..end`
+        let first_in = |start: usize, end: usize| {
+            let mut i = start;
+            while i < end {
+                let word = bits[i / 64] >> (i % 64);
+                if word != 0 {
+                    let found = i + word.trailing_zeros() as usize;
+                    return (found < end).then_some(found);
+                }
+                i = (i / 64 + 1) * 64;
+            }
+            None
+        };
+        let size = self.config.wheel_size();
+        first_in(from, size)
+            .map(|slot| slot - from)
+            .or_else(|| first_in(0, from).map(|slot| slot + size - from))
+    }
+
+    /// First entry of the list in `(level, slot)`
+    #[inline]
+    fn head(&self, level: usize, slot: usize) -> Option<NonZeroU32> {
+        self.wheels[self.bucket(level, slot)]
+    }
+
+    /// Sets how many overflow timers may be promoted into the wheels per tick.
+    /// A budget of 0 only promotes timers that are about to become due.
+    pub fn set_promotion_budget(&mut self, per_tick: usize) {
+        self.promotion_budget = per_tick;
+    }
+
+    /// Earliest deadline that is still in the future. Earlier ones fire on the next tick.
+    pub fn min_deadline(&self) -> u64 {
+        self.current_tick
+    }
+
+    /// Latest deadline the wheels hold directly for the current configuration, i.e.
+    /// `current_time() + horizon - 1`. Later deadlines are accepted but parked in the
+    /// overflow set until they come within range.
+    pub fn max_deadline(&self) -> u64 {
+        self.current_tick.saturating_add(self.config.horizon() - 1)
+    }
+
+    /// Number of timers currently parked beyond the horizon
+    pub fn overflow_len(&self) -> usize {
+        self.overflow.len()
+    }
 
-        // 4. Finally free the memory and return task
-        self.slab.free(idx)
+    /// Number of timers linked into each level, level 0 first, e.g. to see how many are
+    /// due soon and how many are parked awaiting a cascade. Kept up to date as timers
+    /// move, so this is O(1). Together with `overflow_len` the counts add up to `len`.
+    pub fn level_counts(&self) -> &[usize] {
+        &self.level_counts
     }
 
-    pub fn process_bucket(&mut self, level: usize, slot: usize, expired: &mut Vec<T>) {
+    /// Empties `(level, slot)`, handing each expired timer to `on_expire` as
+    /// `(handle, deadline, task)` and cascading the rest down
+    fn process_bucket_with<F: FnMut(Key, u64, T)>(
+        &mut self,
+        level: usize,
+        slot: usize,
+        on_expire: &mut F,
+    ) {
         // STEAL the list. The bucket is now empty (None).
         // This allows us to modify the slab while iterating the stolen indices.
-        let mut next_idx = self.wheels[level][slot].take();
+        let bucket = self.bucket(level, slot);
+        let head = self.wheels[bucket].take();
+        let tail = self.tails[bucket].take();
+        self.mark_occupied(level, slot, false);
 
-        // Walk the linked list
+        // Walk the linked list. Level 0 fires front to back; upper levels are walked back
+        // to front because cascading prepends, which keeps the list order in the target.
+        let backward = level > 0;
+        let mut next_idx = if backward { tail } else { head };
         while let Some(curr_idx) = next_idx {
-            // 1. Get metadata and drop reference
-            let (deadline, next_node) = {
-                let entry = self.slab.get(curr_idx).unwrap();
-                (entry.deadline, entry.next)
+            // 1. Get metadata and drop reference. Expired or cascaded, it leaves `level`.
+            self.level_counts[level] -= 1;
+            let (deadline, next_node, approximate) = {
+                let entry = self.slab.entry(curr_idx).unwrap();
+                let step = if backward { entry.prev } else { entry.next };
+                (entry.deadline, step, entry.approximate)
             };
 
-            // 2. Logic: Expire or Cascade
-            if deadline <= self.current_tick {
+            // The bucket must cover the current span of its level, or the cascade schedule
+            // has visited it too late (or too early) for this timer
+            let shift = self.config.shift(level);
+            debug_assert!(
+                deadline <= self.current_tick
+                    || (deadline >> shift == self.current_tick >> shift
+                        && ((deadline >> shift) & self.config.mask()) as usize == slot),
+                "timer due at {deadline} found in level {level} slot {slot} at tick {}",
+                self.current_tick
+            );
+
+            // 2. Logic: Expire or Cascade. Only level 0 expires exact timers: an upper bucket
+            // is cascaded by the tick entering its span, before any of its timers is due.
+            // Approximate timers fire right here instead of paying for the cascade.
+            if (level == 0 && deadline <= self.current_tick) || approximate {
                 // Expired: Remove and return
-                if let Some(task) = self.slab.free(curr_idx) {
-                    expired.push(task);
+                if let Some(handle) = self.slab.key_at(curr_idx)
+                    && let Some(task) = self.expire_detached(curr_idx)
+                {
+                    #[cfg(feature = "debug-events")]
+                    self.events.record(WheelEvent::Expire { handle, deadline });
+                    self.stats.total_expired += 1;
+                    on_expire(handle, deadline, task);
                 }
             } else {
-                // Not expired! Re-insert to the correct wheel (Cascading).
-                // extract the task and re-insert it. This handles the new level calculation.
-                if let Some(task) = self.slab.free(curr_idx) {
-                    self.insert(task, deadline);
+                // Not expired! Re-link into the correct wheel (Cascading).
+                // The entry stays in its slab slot, so the caller's handle remains valid.
+                // Being in the current span of `level`, the deadline is less than a span away,
+                // so it always lands strictly lower and never back in the stolen bucket.
+                let to_level = self.link_cascaded(curr_idx);
+                debug_assert!((to_level as usize) < level, "cascade did not descend");
+                self.stats.total_cascades += 1;
+
+                #[cfg(feature = "debug-events")]
+                if let Some(handle) = self.slab.key_at(curr_idx) {
+                    self.events.record(WheelEvent::Cascade {
+                        handle,
+                        deadline,
+                        from_level: level as u8,
+                        to_level,
+                    });
                 }
             }
 
@@ -130,66 +1103,880 @@ impl<T> TimingWheel<T> {
 
     /// Core Tick Algorithm
     /// Advances time by 1 tick and returns all expired timers
+    ///
+    /// Time saturates at `u64::MAX` rather than wrapping: that tick can be processed
+    /// any number of times, firing whatever is due by then, but the clock stays there.
     pub fn tick(&mut self, expired: &mut Vec<T>) {
-        // Step 1: Process Level 0, current slot
-        let slot0 = (self.current_tick & WHEEL_MASK) as usize;
-        self.process_bucket(0, slot0, expired);
-        // Step 2: Advance current tick
-        self.current_tick += 1;
-
-        // Step 3: Cascade Check
-        let tick = self.current_tick;
+        self.tick_into(|_, _, task| expired.push(task));
+    }
 
-        // Check level 1 (Wrapped if lower 6 bits are 0)
-        if (tick & WHEEL_MASK) == 0 {
-            let slot1 = ((tick >> WHEEL_BITS) & WHEEL_MASK) as usize;
-            self.process_bucket(1, slot1, expired);
+    /// Advances one tick and returns `true` if the tick has nothing to do: no timer due
+    /// in the current level-0 slot and no cascade, overflow promotion or auto-shrink
+    /// check on the way to the next one. Otherwise returns `false` without moving time,
+    /// leaving the tick to `tick` and its buffer. As with the ticks `advance_to` skips,
+    /// fast ticks don't show up in the debug event log.
+    ///
+    /// ```
+    /// use sharded_timing_wheel::TimingWheel;
+    ///
+    /// let mut wheel = TimingWheel::new();
+    /// wheel.insert("due", 2);
+    /// let mut expired = Vec::new();
+    /// while expired.is_empty() {
+    ///     if !wheel.tick_empty_fast() {
+    ///         wheel.tick(&mut expired);
+    ///     }
+    /// }
+    /// assert_eq!((wheel.current_time(), expired), (3, vec!["due"]));
+    /// ```
+    pub fn tick_empty_fast(&mut self) -> bool {
+        if self.paused {
+            return true;
         }
-
-        // Check level 2 (Wrapped if lower 12 bits are 0)
-        // Use 1u64 to ensure type safety during shift
-        if (tick & ((1u64 << (2 * WHEEL_BITS)) - 1)) == 0 {
-            let slot2 = ((tick >> (2 * WHEEL_BITS)) & WHEEL_MASK) as usize;
-            self.process_bucket(2, slot2, expired);
+        if self.next_busy_tick() > self.current_tick {
+            // Only an increment; below `next_busy_tick` the clock can't be at u64::MAX
+            self.current_tick += 1;
+            true
+        } else {
+            false
         }
+    }
 
-        // Check level 3 (Wrapped if lower 18 bits are 0)
-        if (tick & ((1u64 << (3 * WHEEL_BITS)) - 1)) == 0 {
-            let slot3 = ((tick >> (3 * WHEEL_BITS)) & WHEEL_MASK) as usize;
-            self.process_bucket(3, slot3, expired);
-        }
+    /// `tick` into a buffer owned by the wheel, for callers that would rather not thread
+    /// one through. Tasks pile up there until `take_expired` hands them out; since the
+    /// buffer keeps its capacity, steady-state ticks don't allocate.
+    pub fn tick_internal(&mut self) {
+        // The buffer can't be borrowed alongside the wheel, so it is moved out for the
+        // tick; `mem::take` leaves an empty Vec behind, which doesn't allocate
+        let mut buffer = core::mem::take(&mut self.expired);
+        self.tick(&mut buffer);
+        self.expired = buffer;
     }
 
-    pub fn current_time(&self) -> u64 {
-        self.current_tick
+    /// Drains the tasks collected by `tick_internal`, oldest first. Tasks not consumed
+    /// from the iterator are dropped with it.
+    pub fn take_expired(&mut self) -> alloc::vec::Drain<'_, T> {
+        self.expired.drain(..)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Like `tick`, but reports the handle each expired timer was inserted with, e.g. to
+    /// drop it from a map of in-flight timers. The handle no longer resolves in the wheel
+    /// and is only good for identification; its slot may already be reused, but the
+    /// generation check keeps it from ever matching the new occupant. Periodic timers
+    /// are the exception: they stay pending under the same handle.
+    pub fn tick_entries(&mut self, expired: &mut Vec<(Key, T)>) {
+        self.tick_into(|key, _, task| expired.push((key, task)));
+    }
 
-    #[test]
-    fn test_basic_insert_and_tick() {
-        let mut wheel = TimingWheel::new();
-        let mut expired = Vec::new(); // 1. Create the buffer
+    /// Like `tick`, but reports each expired task with the deadline it was scheduled
+    /// for, e.g. to measure lateness or re-arm periodic work. Exact timers always have
+    /// `deadline < current_time()` afterwards; approximate ones may report a deadline
+    /// that is still ahead, since they fire when their upper bucket is reached.
+    pub fn tick_deadlines(&mut self, expired: &mut Vec<(u64, T)>) {
+        self.tick_into(|_, deadline, task| expired.push((deadline, task)));
+    }
 
-        wheel.insert("task1", 5);
-        wheel.insert("task2", 10);
-        wheel.insert("task3", 2);
+    /// Like `tick`, but the tasks expired by this tick are appended in ascending deadline
+    /// order. That matters when one tick fires timers with different deadlines, such as
+    /// overdue inserts or approximate timers. Equal deadlines keep the order `tick` uses.
+    pub fn tick_sorted(&mut self, expired: &mut Vec<T>) {
+        let mut due = Vec::new();
+        self.tick_deadlines(&mut due);
+        // Stable, so ties stay in bucket order
+        due.sort_by_key(|&(deadline, _)| deadline);
+        expired.extend(due.into_iter().map(|(_, task)| task));
+    }
 
-        // Tick 0 -> 1
-        wheel.tick(&mut expired);
-        wheel.tick(&mut expired);
-        assert!(expired.is_empty()); // Verify nothing happened yet
+    /// One full tick, handing every expired timer to `on_expire`
+    fn tick_into<F: FnMut(Key, u64, T)>(&mut self, mut on_expire: F) {
+        if self.paused {
+            return;
+        }
+        #[cfg(feature = "debug-events")]
+        self.events.record(WheelEvent::Tick {
+            tick: self.current_tick,
+        });
 
-        // Tick 2 (Should fire task3)
-        wheel.tick(&mut expired);
-        assert_eq!(expired.len(), 1);
-        assert_eq!(expired[0], "task3");
+        // Step 1: Process Level 0, current slot
+        let slot0 = (self.current_tick & self.config.mask()) as usize;
+        self.process_bucket_with(0, slot0, &mut on_expire);
+        self.finish_tick_with(&mut on_expire);
+    }
 
-        // Clear the buffer to check the next specific event
-        expired.clear();
+    /// Like `tick`, but hands the expired tasks out through an iterator instead of a
+    /// buffer. The current level-0 slot is drained one timer per `next()`; the cascade
+    /// runs once it is empty. Dropping the iterator early still completes the tick, and
+    /// any task not yet yielded is dropped.
+    pub fn tick_iter(&mut self) -> impl Iterator<Item = T> + '_ {
+        #[cfg(feature = "debug-events")]
+        if !self.paused {
+            self.events.record(WheelEvent::Tick {
+                tick: self.current_tick,
+            });
+        }
+
+        TickIter {
+            slot: (self.current_tick & self.config.mask()) as usize,
+            // A paused tick yields nothing and leaves the clock alone
+            finished: self.paused,
+            wheel: self,
+            spill: Vec::new().into_iter(),
+        }
+    }
+
+    /// Like `tick`, but hands each expired task straight to `on_expire` instead of
+    /// collecting them, so a tick loop needs no buffer at all. The wheel stays mutably
+    /// borrowed for the whole call, so the callback cannot reach into it.
+    ///
+    /// If `on_expire` panics, the tick is still completed while unwinding: the timers of
+    /// the current slot that were not handed out yet are dropped without firing, the
+    /// clock advances and the cascade runs, leaving the wheel fully consistent.
+    pub fn tick_with<F: FnMut(T)>(&mut self, on_expire: F) {
+        self.tick_iter().for_each(on_expire);
+    }
+
+    /// Like `tick`, but delivers at most `max` timers from the current slot per call, so a
+    /// mass expiry can be spread over several calls. Returns `true` while due timers are
+    /// left: the undelivered ones stay linked in their slot and `current_time()` does not
+    /// move until a later call has delivered them all. The call that finishes the tick
+    /// also runs the cascade, and approximate timers firing there exceed the budget.
+    pub fn tick_budgeted(&mut self, expired: &mut Vec<T>, max: usize) -> bool {
+        if self.paused {
+            return false;
+        }
+        #[cfg(feature = "debug-events")]
+        self.events.record(WheelEvent::Tick {
+            tick: self.current_tick,
+        });
+
+        let slot0 = (self.current_tick & self.config.mask()) as usize;
+        for _ in 0..max {
+            match self.pop_due(slot0) {
+                Some(task) => expired.push(task),
+                None => break,
+            }
+        }
+        if self.head(0, slot0).is_some() {
+            return true;
+        }
+        self.finish_tick(expired);
+        false
+    }
+
+    /// Removes and returns the first timer in level-0 `slot`, which is due by construction
+    fn pop_due(&mut self, slot: usize) -> Option<T> {
+        let idx = self.head(0, slot)?;
+        self.unlink(idx);
+
+        #[cfg(feature = "debug-events")]
+        if let Some(handle) = self.slab.key_at(idx) {
+            let deadline = self.slab.entry(idx).map_or(0, |entry| entry.deadline);
+            self.events.record(WheelEvent::Expire { handle, deadline });
+        }
+
+        self.stats.total_expired += 1;
+        self.expire_detached(idx)
+    }
+
+    /// Hands out the task of a due entry already taken off its list. One-shot timers are
+    /// freed; periodic ones keep their slot and handle, give out a clone and are linked
+    /// again for their next period. At the saturated last tick there is no later period,
+    /// and re-linking would put the entry back into the slot being drained, so a periodic
+    /// timer is freed there like a one-shot.
+    fn expire_detached(&mut self, idx: NonZeroU32) -> Option<T> {
+        let now = self.current_tick;
+        let entry = self.slab.entry_mut(idx)?;
+        if let Some(interval) = entry.interval
+            && let Some(clone_task) = self.clone_task
+            && now < u64::MAX
+        {
+            let next = entry.deadline.saturating_add(interval.get());
+            entry.deadline = next.max(now + 1);
+            let task = clone_task(&entry.task);
+            self.link(idx);
+            return Some(task);
+        }
+        self.slab.free_at(idx)
+    }
+
+    /// Second half of a tick, once the level-0 slot is done: advance the clock, cascade
+    /// the levels that wrapped and run the per-tick housekeeping
+    fn finish_tick(&mut self, expired: &mut Vec<T>) {
+        self.finish_tick_with(&mut |_, _, task| expired.push(task));
+    }
+
+    fn finish_tick_with<F: FnMut(Key, u64, T)>(&mut self, on_expire: &mut F) {
+        let mask = self.config.mask();
+        // Step 2: Advance current tick. Deadlines are absolute and compared as plain
+        // integers everywhere, so wrapping to 0 would make every pending timer look
+        // a full u64 away; stopping at the last tick keeps them firing in order.
+        self.current_tick = self.current_tick.saturating_add(1);
+
+        // Step 3: Cascade Check
+        let tick = self.current_tick;
+
+        // Level N wraps when the lower N * wheel_bits bits of the tick are all 0, which
+        // also means every level below it wrapped
+        for level in 1..self.config.num_levels as usize {
+            let shift = self.config.shift(level);
+            if tick & ((1u64 << shift) - 1) != 0 {
+                break;
+            }
+            let slot = ((tick >> shift) & mask) as usize;
+            self.process_bucket_with(level, slot, on_expire);
+        }
+
+        // Pull in a bounded number of overflow timers that are now in range
+        if !self.overflow.is_empty() {
+            self.promote_overflow();
+        }
+
+        if let Some(policy) = self.shrink_policy
+            && tick.is_multiple_of(policy.check_interval)
+        {
+            self.maybe_shrink(policy);
+        }
+    }
+
+    /// Applies the auto-shrink policy: if utilization fell below the target, release
+    /// storage down to what the live entries need at that target.
+    fn maybe_shrink(&mut self, policy: ShrinkPolicy) {
+        let capacity = self.slab.capacity();
+        let target = policy.target_utilization as usize;
+        if self.slab.len() * 100 >= capacity * target {
+            return;
+        }
+
+        let wanted = (self.slab.len() * 100 / target).max(MIN_SHRINK_CAPACITY);
+        if wanted < capacity && self.slab.shrink_to(wanted) {
+            self.stats.total_shrinks += 1;
+            if let Some(observer) = self.observer.as_mut() {
+                observer.on_slab_shrink(capacity, self.slab.capacity());
+            }
+        }
+    }
+
+    /// Number of pending timers, including ones parked beyond the horizon.
+    /// Cascading a timer between levels does not change it.
+    pub fn len(&self) -> usize {
+        self.slab.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slab.is_empty()
+    }
+
+    /// Slots the slab can hold before it has to grow
+    pub fn capacity(&self) -> usize {
+        self.slab.capacity()
+    }
+
+    /// Releases the timer storage left over from a burst, down to the last pending timer.
+    /// Pending timers never move, so every live handle stays valid; if one sits near the
+    /// end of the slab, the space before it cannot be released.
+    pub fn shrink_to_fit(&mut self) {
+        self.slab.shrink_to_fit();
+    }
+
+    /// How many times the auto-shrink policy has released memory
+    pub fn shrink_count(&self) -> u64 {
+        self.stats.total_shrinks
+    }
+
+    /// Snapshot of the lifetime counters, e.g. to export as metrics. Many cascades per
+    /// expiry suggest the resolution is finer than the timeouts need.
+    pub fn stats(&self) -> WheelStats {
+        WheelStats {
+            current_live: self.len(),
+            #[cfg(feature = "alloc-tagging")]
+            heap_bytes: self.heap_bytes(),
+            ..self.stats
+        }
+    }
+
+    /// Drops every pending timer without firing it and resets the wheel to tick 0,
+    /// re-anchored at `Instant::now()`, as if it had just been created.
+    ///
+    /// Runs in O(capacity) and keeps the slab's allocation for reuse. Handles issued
+    /// before the clear never resolve again, and new ones start from the first slot.
+    pub fn clear(&mut self) {
+        self.wheels.fill(None);
+        self.tails.fill(None);
+        self.occupied.fill(0);
+        self.level_counts.fill(0);
+        self.overflow.clear();
+        self.slab.clear();
+        self.current_tick = 0;
+        #[cfg(feature = "std")]
+        {
+            self.origin = Instant::now();
+        }
+    }
+
+    /// Renumbers time so that the current tick becomes tick 0, e.g. in a long-running
+    /// process that wants deadlines to stay small. Every deadline moves back by the old
+    /// `current_time()` (overdue ones become 0) and every timer is re-slotted for the
+    /// new numbering; handles stay valid. The wall-clock mapping moves along, so
+    /// `insert_after` and `tick_to_instant` refer to the same instants as before.
+    ///
+    /// Exact timers fire in the same order and the same number of ticks from now as
+    /// they would have without the rebase. Approximate timers fire when their bucket
+    /// comes up, which depends on the numbering, so they may fire at other ticks within
+    /// their usual earliness. Runs in O(capacity + levels * wheel_size) and reports a
+    /// single `on_bulk_reschedule` to the observer.
+    pub fn rebase(&mut self) {
+        let shift = self.current_tick;
+        if shift == 0 {
+            return;
+        }
+
+        // Upper levels first, each list front to back. Equal deadlines can sit in
+        // different levels (the earlier insert has not cascaded yet) but land in one
+        // bucket now, where linking in this order keeps the earlier insert in front.
+        let mut order = Vec::with_capacity(self.len() - self.overflow.len());
+        for level in (0..self.config.num_levels as usize).rev() {
+            for slot in 0..self.config.wheel_size() {
+                let mut cursor = self.head(level, slot);
+                while let Some(idx) = cursor {
+                    order.push(idx);
+                    cursor = self.slab.entry(idx).and_then(|entry| entry.next);
+                }
+            }
+        }
+
+        self.wheels.fill(None);
+        self.tails.fill(None);
+        self.occupied.fill(0);
+        self.level_counts.fill(0);
+        for (_, entry) in self.slab.iter_mut() {
+            entry.deadline = entry.deadline.saturating_sub(shift);
+        }
+        // Durations are unchanged, so parked timers stay parked, just renumbered
+        self.overflow = core::mem::take(&mut self.overflow)
+            .into_iter()
+            .map(|(deadline, idx)| (deadline.saturating_sub(shift), idx))
+            .collect();
+        #[cfg(feature = "std")]
+        {
+            self.origin = self.tick_to_instant(shift);
+        }
+        self.current_tick = 0;
+        for idx in order {
+            self.link(idx);
+        }
+
+        if let Some(observer) = self.observer.as_mut() {
+            observer.on_bulk_reschedule(self.slab.len());
+        }
+    }
+
+    /// Keeps only the timers for which `keep(task, deadline)` returns `true`, dropping the
+    /// rest, e.g. every timer belonging to a tenant that disconnected. Covers every level
+    /// and the overflow set; surviving timers keep their handles and deadlines.
+    /// Returns how many timers were dropped.
+    pub fn retain<F: FnMut(&T, u64) -> bool>(&mut self, mut keep: F) -> usize {
+        let before = self.len();
+        for bucket in 0..self.wheels.len() {
+            let mut next_idx = self.wheels[bucket];
+            while let Some(idx) = next_idx {
+                let entry = self.slab.entry(idx).expect("bucket links a free slot");
+                next_idx = entry.next;
+                if !keep(&entry.task, entry.deadline) {
+                    // Fix up the neighbours before the slot is freed
+                    self.unlink(idx);
+                    drop(self.slab.free_at(idx));
+                    self.stats.total_cancelled += 1;
+                }
+            }
+        }
+
+        let slab = &self.slab;
+        let mut rejected = Vec::new();
+        self.overflow.retain(|&(deadline, idx)| {
+            let entry = slab.entry(idx).expect("overflow links a free slot");
+            let kept = keep(&entry.task, deadline);
+            if !kept {
+                rejected.push(idx);
+            }
+            kept
+        });
+        self.stats.total_cancelled += rejected.len() as u64;
+        for idx in rejected {
+            drop(self.slab.free_at(idx));
+        }
+        before - self.len()
+    }
+
+    /// Shutdown flush: removes every pending timer and pushes its task into `expired`
+    /// right away, whatever its deadline, so owners still get to run their cleanup.
+    ///
+    /// Tasks arrive in deadline order, as if the wheel had run to completion. The clock
+    /// is left where it is and the wheel is empty afterwards.
+    pub fn expire_all(&mut self, expired: &mut Vec<T>) {
+        let mut pending: Vec<_> = self.drain().collect();
+        // Stable, so timers sharing a deadline keep slot order
+        pending.sort_by_key(|&(deadline, _)| deadline);
+        self.stats.total_expired += pending.len() as u64;
+        expired.extend(pending.into_iter().map(|(_, task)| task));
+    }
+
+    /// Drops every timer currently linked into `level`, leaving the other levels, the
+    /// overflow set and `current_time()` alone. Their handles become stale.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `level` is not below the number of levels.
+    pub fn clear_level(&mut self, level: usize) {
+        assert!(
+            level < self.config.num_levels as usize,
+            "level {level} out of range"
+        );
+
+        for slot in 0..self.config.wheel_size() {
+            let bucket = self.bucket(level, slot);
+            let mut next_idx = self.wheels[bucket].take();
+            self.tails[bucket] = None;
+            self.mark_occupied(level, slot, false);
+            self.level_counts[level] = 0;
+            while let Some(idx) = next_idx {
+                next_idx = self.slab.entry(idx).and_then(|entry| entry.next);
+                // The slot is already unlinked and freed when the task drops
+                drop(self.slab.free_at(idx));
+                self.stats.total_cancelled += 1;
+            }
+        }
+    }
+
+    /// Removes every pending timer, yielding its absolute deadline and task, e.g. to hand
+    /// timers off during a graceful shutdown.
+    ///
+    /// Each timer appears exactly once regardless of the level it is parked in, but the
+    /// order is unspecified. Afterwards the wheel is empty and can be reused.
+    pub fn drain(&mut self) -> impl Iterator<Item = (u64, T)> + '_ {
+        self.wheels.fill(None);
+        self.tails.fill(None);
+        self.occupied.fill(0);
+        self.level_counts.fill(0);
+        self.overflow.clear();
+        self.slab.drain()
+    }
+
+    /// Advances the wheel until `current_time() == target`, collecting every expired timer.
+    ///
+    /// Stretches where no bucket needs processing are skipped in one step, so a long jump
+    /// costs time proportional to the occupied buckets crossed rather than to the ticks
+    /// crossed. Skipped ticks do not show up in the debug event log.
+    ///
+    /// Time never moves backwards: a `target` behind the current tick (e.g. a wall clock
+    /// stepped back by NTP) is rejected with `ClockWentBackwards` and nothing is processed.
+    /// Callers that drive the wheel from a clock should keep ticking from `current_time()`
+    /// until the clock catches up again.
+    pub fn advance_to(
+        &mut self,
+        target: u64,
+        expired: &mut Vec<T>,
+    ) -> Result<(), ClockWentBackwards> {
+        self.advance_to_with(target, |_, _, task| expired.push(task))
+    }
+
+    /// `advance_to`, handing every expired timer with its handle and deadline to
+    /// `on_expire`
+    pub(crate) fn advance_to_with<F: FnMut(Key, u64, T)>(
+        &mut self,
+        target: u64,
+        mut on_expire: F,
+    ) -> Result<(), ClockWentBackwards> {
+        if target < self.current_tick {
+            return Err(ClockWentBackwards {
+                by: self.current_tick - target,
+            });
+        }
+        if self.paused {
+            return Ok(());
+        }
+
+        while self.current_tick < target {
+            let busy = self.next_busy_tick();
+            if busy > self.current_tick {
+                // Nothing happens on the ticks in between; jump straight over them
+                self.current_tick = busy.min(target);
+                continue;
+            }
+            self.tick_into(&mut on_expire);
+        }
+        Ok(())
+    }
+
+    /// Jumps straight to the next tick with any work to do (an expiry, a cascade or an
+    /// overflow promotion), performs it and returns the new `current_time()`. Some of
+    /// those ticks only move timers between levels and expire nothing.
+    ///
+    /// Returns `None` without moving time if the wheel is empty.
+    pub fn skip_to_next_event(&mut self, expired: &mut Vec<T>) -> Option<u64> {
+        if self.is_empty() || self.paused {
+            return None;
+        }
+        self.current_tick = self.next_busy_tick();
+        self.tick(expired);
+        Some(self.current_tick)
+    }
+
+    /// Advances the wheel by `n` ticks, collecting every expired timer. The result is the
+    /// same as calling `tick` `n` times, but idle stretches are skipped as in `advance_to`.
+    pub fn tick_n(&mut self, n: u64, expired: &mut Vec<T>) {
+        let target = self.current_tick.saturating_add(n);
+        // Moving forward from the current tick cannot fail
+        let _ = self.advance_to(target, expired);
+    }
+
+    /// Advances `n` ticks one at a time, calling `on_tick(tick, expired)` after each with
+    /// the tick just processed and the tasks that fired on it, e.g. to dispatch every
+    /// tick's batch under a rate limit. `on_tick` runs for empty ticks too. The buffer is
+    /// cleared between calls, so tasks left in it are dropped.
+    pub fn tick_n_with<F: FnMut(u64, &mut Vec<T>)>(&mut self, n: u64, mut on_tick: F) {
+        let mut expired = Vec::new();
+        for _ in 0..n {
+            let tick = self.current_tick;
+            self.tick(&mut expired);
+            on_tick(tick, &mut expired);
+            expired.clear();
+        }
+    }
+
+    /// The earliest `current_time()` at which `tick` has real work to do: a level-0 slot
+    /// to expire, an upper-level bucket to cascade, an overflow timer to promote or an
+    /// auto-shrink check. Every tick before it only increments the counter, so batch
+    /// advances may skip them. `u64::MAX` if nothing is ever due.
+    fn next_busy_tick(&self) -> u64 {
+        let now = self.current_tick;
+        let mut busy = u64::MAX;
+
+        let mask = self.config.mask();
+
+        let slot0 = (now & mask) as usize;
+        if let Some(offset) = self.next_occupied(0, slot0) {
+            busy = now + offset as u64;
+        }
+
+        for level in 1..self.config.num_levels as usize {
+            let shift = self.config.shift(level);
+            let base = now >> shift;
+            // Bucket `m` slots ahead is cascaded by the tick that crosses into it. The
+            // current bucket counts as a full rotation ahead: it was cascaded already.
+            let after = ((base + 1) & mask) as usize;
+            if let Some(offset) = self.next_occupied(level, after) {
+                let m = offset as u64 + 1;
+                busy = busy.min(((base + m) << shift) - 1);
+            }
+        }
+
+        if let Some(&(deadline, _)) = self.overflow.first() {
+            // Without a budget the earliest timer only moves once it is urgent
+            let window = if self.promotion_budget == 0 {
+                self.urgent_promotion()
+            } else {
+                self.config.horizon()
+            };
+            busy = busy.min(deadline.saturating_sub(window).max(now));
+        }
+
+        if let Some(policy) = self.shrink_policy {
+            // No check left before the clock saturates: nothing to wait for
+            let next_check = now
+                .checked_add(1)
+                .and_then(|tick| tick.checked_next_multiple_of(policy.check_interval));
+            busy = busy.min(next_check.map_or(u64::MAX, |tick| tick - 1));
+        }
+        busy
+    }
+
+    /// Like `advance_to`, but calls `on_tick(current_time())` after every tick crossed,
+    /// e.g. to update per-tick metrics during a batch advance. Idle ticks are not skipped.
+    pub fn tick_to_with<F: FnMut(u64)>(
+        &mut self,
+        target: u64,
+        mut on_tick: F,
+        expired: &mut Vec<T>,
+    ) -> Result<(), ClockWentBackwards> {
+        if target < self.current_tick {
+            return Err(ClockWentBackwards {
+                by: self.current_tick - target,
+            });
+        }
+        if self.paused {
+            return Ok(());
+        }
+
+        while self.current_tick < target {
+            self.tick(expired);
+            on_tick(self.current_tick);
+        }
+        Ok(())
+    }
+
+    /// The earliest deadline of any pending timer, or `None` if the wheel is empty.
+    /// Lets an event loop sleep until that tick instead of ticking blindly.
+    ///
+    /// Probes at most `num_levels * wheel_size` slots, walking only the first occupied
+    /// bucket of each level. Higher levels are still consulted when level 0 has a
+    /// match, since an entry waiting to cascade may be due before it.
+    pub fn peek_next_deadline(&self) -> Option<u64> {
+        let mut earliest = self.overflow.first().map(|&(deadline, _)| deadline);
+
+        let size = self.config.wheel_size();
+        for level in 0..self.config.num_levels as usize {
+            let shift = self.config.shift(level);
+            let current = ((self.current_tick >> shift) & self.config.mask()) as usize;
+            // Level 0's current slot is due this tick. On the upper levels it was just
+            // cascaded, so anything there now belongs to the next rotation and comes last.
+            let first = if level == 0 { 0 } else { 1 };
+            let bucket = self
+                .next_occupied(level, (current + first) % size)
+                .and_then(|offset| self.head(level, (current + first + offset) % size));
+
+            if let Some(head) = bucket {
+                let bucket_min = self.bucket_min_deadline(head);
+                earliest = Some(earliest.map_or(bucket_min, |e| e.min(bucket_min)));
+            }
+        }
+        earliest
+    }
+
+    /// Smallest deadline in the bucket list starting at `head`
+    fn bucket_min_deadline(&self, head: NonZeroU32) -> u64 {
+        self.bucket_entries(Some(head))
+            .map(|entry| entry.deadline)
+            .min()
+            .unwrap_or(u64::MAX)
+    }
+
+    /// Read-only walk of the bucket list starting at `head`
+    fn bucket_entries(
+        &self,
+        head: Option<NonZeroU32>,
+    ) -> impl Iterator<Item = &TimerEntry<T>> + '_ {
+        let entry_at = |idx: NonZeroU32| self.slab.entry(idx).expect("bucket links a free slot");
+        core::iter::successors(head.map(entry_at), move |entry| entry.next.map(entry_at))
+    }
+
+    /// Number of timers linked into every slot, indexed `[level][slot]`. Timers parked
+    /// in the overflow set are not included; see `overflow_len`.
+    ///
+    /// Walks every list, so it is meant for tests and debugging rather than hot paths.
+    pub fn dump_occupancy(&self) -> Vec<Vec<usize>> {
+        (0..self.config.num_levels as usize)
+            .map(|level| {
+                (0..self.config.wheel_size())
+                    .map(|slot| self.bucket_entries(self.head(level, slot)).count())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Blocks the current thread until the next timer is due, then advances past it and
+    /// returns the tick it fired on together with every task that expired.
+    ///
+    /// Returns `(current_time(), vec![])` immediately if nothing is pending.
+    /// Timers already overdue are returned without sleeping. A deadline too far out to
+    /// be an `Instant` at all is waited on for at most a day at a time: the call then
+    /// returns `(current_time(), vec![])` with nothing fired, and the caller just calls
+    /// again.
+    #[cfg(feature = "std")]
+    pub fn wait_next(&mut self) -> (u64, Vec<T>) {
+        self.wait_next_with(&SystemClock)
+    }
+
+    /// `wait_next` against an explicit clock, e.g. a `MockClock` in tests.
+    ///
+    /// If the clock reads earlier than the current tick (e.g. the wall clock was stepped
+    /// back), the wheel re-anchors so the current tick maps to the clock's `now()`. The
+    /// tick counter never rewinds, so every pending timer still fires exactly once, each
+    /// after the same number of ticks it had left.
+    #[cfg(feature = "std")]
+    pub fn wait_next_with<C: Clock>(&mut self, clock: &C) -> (u64, Vec<T>) {
+        if self.paused {
+            return (self.current_tick, Vec::new());
+        }
+        let Some(earliest) = self.peek_next_deadline() else {
+            return (self.current_tick, Vec::new());
+        };
+        let due = earliest.max(self.current_tick);
+
+        self.reanchor(clock.now());
+        let Some(wake) = self.checked_tick_to_instant(due) else {
+            if let Some(wake) = clock.now().checked_add(MAX_WAIT) {
+                clock.sleep_until(wake);
+            }
+            return (self.current_tick, Vec::new());
+        };
+        clock.sleep_until(wake);
+
+        let mut expired = Vec::new();
+        // Time only moves forward here, so this cannot fail. The final `tick` processes
+        // `due` itself, even when it is the saturated last tick.
+        let _ = self.advance_to(due, &mut expired);
+        self.tick(&mut expired);
+        (due, expired)
+    }
+
+    /// Moves the origin back so the current tick maps to `now`, if `now` is earlier than it
+    #[cfg(feature = "std")]
+    fn reanchor(&mut self, now: Instant) {
+        let Some(anchored) = self.checked_tick_to_instant(self.current_tick) else {
+            return;
+        };
+        if now < anchored
+            && let Some(origin) = self.origin.checked_sub(anchored - now)
+        {
+            self.origin = origin;
+        }
+    }
+
+    /// The level layout this wheel was built with
+    pub fn config(&self) -> WheelConfig {
+        self.config
+    }
+
+    /// Freezes the clock without touching any timer, e.g. for a maintenance window.
+    /// While paused, every call that moves time (the `tick` family, `advance_to`,
+    /// `tick_n`, `skip_to_next_event`, `wait_next`) returns without doing anything and
+    /// nothing fires. Inserts, cancels and reschedules still work, relative to the
+    /// frozen `current_time()`.
+    ///
+    /// Deadlines are in wheel ticks, so after `resume` every timer is effectively
+    /// pushed back by the length of the pause; `resume_and_catch_up` counts the paused
+    /// time instead.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Lets time move again, from where it stopped
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Resumes and immediately advances to `target`, firing everything that came due
+    /// during the pause, as if the wheel had kept running
+    pub fn resume_and_catch_up(
+        &mut self,
+        target: u64,
+        expired: &mut Vec<T>,
+    ) -> Result<(), ClockWentBackwards> {
+        self.resume();
+        self.advance_to(target, expired)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// The tick the wheel is at: the next `tick` processes this one.
+    ///
+    /// The counter saturates at `u64::MAX` and never wraps. Deadlines are absolute and
+    /// compared as plain integers everywhere (the overflow set's order, `FirePolicy`,
+    /// `peek_next_deadline`, the skip-ahead in `advance_to`), so a wrapped clock would
+    /// treat every pending timer as a full `u64` away. A wrapping mode would need all of
+    /// those to compare relative to the current tick, for a counter that lasts 584 years
+    /// even at a 1ns resolution, so it is deliberately not offered.
+    pub fn current_time(&self) -> u64 {
+        self.current_tick
+    }
+
+    /// Approximate heap footprint of the wheel, e.g. to size how many wheels fit in a
+    /// memory budget: the slab and the bucket arrays by capacity, plus the overflow set
+    /// and the `tick_internal` buffer. Memory owned by the tasks themselves, the observer
+    /// and the debug event log is not counted, nor are the overflow set's node overheads.
+    pub fn memory_bytes(&self) -> usize {
+        let links = size_of::<Option<NonZeroU32>>();
+        self.slab.memory_bytes()
+            + (self.wheels.capacity() + self.tails.capacity()) * links
+            + self.occupied.capacity() * size_of::<u64>()
+            + self.level_counts.capacity() * size_of::<usize>()
+            + self.overflow.len() * size_of::<(u64, NonZeroU32)>()
+            + self.expired.capacity() * size_of::<T>()
+    }
+
+    /// Bytes of heap currently held by this wheel's timer storage, by capacity: the part
+    /// of `memory_bytes` that the slab's entries account for
+    #[cfg(feature = "alloc-tagging")]
+    pub fn heap_bytes(&self) -> usize {
+        self.slab.heap_bytes()
+    }
+
+    /// Installs an observer notified of deadline changes and overflow parking,
+    /// replacing any previous one
+    pub fn set_observer(&mut self, observer: impl WheelObserver + 'static) {
+        self.observer = Some(Box::new(observer));
+    }
+
+    /// Removes the installed observer, if any
+    pub fn take_observer(&mut self) -> Option<Box<dyn WheelObserver>> {
+        self.observer.take()
+    }
+
+    /// Wall-clock length of one tick
+    pub fn resolution(&self) -> Duration {
+        self.resolution
+    }
+
+    /// Maps a tick back to the wall-clock instant it represents
+    ///
+    /// # Panics
+    ///
+    /// Panics if that instant is too far out for `Instant`, e.g. a saturated deadline at
+    /// a coarse resolution; see `checked_tick_to_instant`.
+    #[cfg(feature = "std")]
+    pub fn tick_to_instant(&self, tick: u64) -> Instant {
+        self.checked_tick_to_instant(tick)
+            .expect("tick is too far out to be represented as an Instant")
+    }
+
+    /// `tick_to_instant`, or `None` if the instant can't be represented
+    #[cfg(feature = "std")]
+    pub fn checked_tick_to_instant(&self, tick: u64) -> Option<Instant> {
+        let nanos = self.resolution.as_nanos() * u128::from(tick);
+        let secs = u64::try_from(nanos / 1_000_000_000).ok()?;
+        let offset = Duration::new(secs, (nanos % 1_000_000_000) as u32);
+        self.origin.checked_add(offset)
+    }
+
+    /// The most recent operations performed on this wheel, oldest first.
+    /// Dump this when a test fails to see the exact sequence that led there.
+    #[cfg(feature = "debug-events")]
+    pub fn recent_events(&self) -> &[WheelEvent] {
+        self.events.recent()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::WheelError;
+
+    // Horizon of the default configuration
+    const HORIZON: u64 = 1 << 24;
+
+    #[test]
+    fn test_basic_insert_and_tick() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new(); // 1. Create the buffer
+
+        wheel.insert("task1", 5);
+        wheel.insert("task2", 10);
+        wheel.insert("task3", 2);
+
+        // Tick 0 -> 1
+        wheel.tick(&mut expired);
+        wheel.tick(&mut expired);
+        assert!(expired.is_empty()); // Verify nothing happened yet
+
+        // Tick 2 (Should fire task3)
+        wheel.tick(&mut expired);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0], "task3");
+
+        // Clear the buffer to check the next specific event
+        expired.clear();
 
         // Tick 3 -> 4
         wheel.tick(&mut expired);
@@ -244,4 +2031,3084 @@ mod tests {
         // "task2" should be there though (at tick 10)
         assert!(expired.contains(&"task2"));
     }
+
+    #[test]
+    fn test_cancel_after_cascade_repairs_bucket_links() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        // Three timers share level 1 slot 1, then cascade into level 0 slot 36 at tick 64
+        let keys: Vec<_> = (0..3).map(|i| wheel.insert(i, 100)).collect();
+        assert!(
+            keys.iter()
+                .all(|&key| wheel.slab.get(key).unwrap().level == 1)
+        );
+        wheel.advance_to(70, &mut expired).unwrap();
+        assert!(
+            keys.iter()
+                .all(|&key| wheel.slab.get(key).unwrap().level == 0)
+        );
+
+        // Walks the level-0 slot, checking every back link against the forward walk
+        let chain = |wheel: &TimingWheel<i32>| {
+            let mut prev = None;
+            let mut tasks = Vec::new();
+            let mut cursor = wheel.head(0, 100 & 63);
+            while let Some(idx) = cursor {
+                let entry = wheel.slab.entry(idx).unwrap();
+                assert_eq!(entry.prev, prev, "dangling back link at {idx}");
+                assert_eq!((entry.level, entry.slot), (0, 100 & 63));
+                tasks.push(entry.task);
+                prev = Some(idx);
+                cursor = entry.next;
+            }
+            assert_eq!(wheel.tails[wheel.bucket(0, 100 & 63)], prev, "stale tail");
+            tasks
+        };
+        assert_eq!(chain(&wheel), vec![0, 1, 2]);
+
+        // Cancel the current head, then the tail, through the original handles
+        let head = wheel.head(0, 100 & 63).unwrap();
+        let head_key = wheel.slab.key_at(head).unwrap();
+        let head_task = wheel.cancel(head_key).unwrap();
+        assert_eq!(chain(&wheel).len(), 2);
+        let tail_task = *chain(&wheel).last().unwrap();
+        let tail_key = keys[tail_task as usize];
+        assert_eq!(wheel.cancel(tail_key), Some(tail_task));
+
+        let left = chain(&wheel);
+        assert_eq!(left.len(), 1);
+        assert!(!left.contains(&head_task) && !left.contains(&tail_task));
+        wheel.advance_to(101, &mut expired).unwrap();
+        assert_eq!(expired, left);
+        assert_eq!(wheel.head(0, 100 & 63), None);
+    }
+
+    #[test]
+    fn test_oneshot_timers_fire_on_their_tick() {
+        let mut wheel = TimingWheel::new();
+        for deadline in [3, 3, 70, 5_000, HORIZON + 2] {
+            wheel.insert_oneshot(deadline, deadline);
+        }
+        assert_eq!(wheel.len(), 5);
+
+        let mut fired = Vec::new();
+        let mut expired = Vec::new();
+        while !wheel.is_empty() {
+            // The returned time is one past the tick that was processed
+            let tick = wheel.skip_to_next_event(&mut expired).unwrap() - 1;
+            fired.extend(expired.drain(..).map(|deadline| (tick, deadline)));
+        }
+        assert!(fired.iter().all(|&(tick, deadline)| tick == deadline));
+        assert_eq!(fired.len(), 5);
+    }
+
+    #[test]
+    fn test_oneshot_timers_share_slots_with_cancellable_ones() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        // Bucket order after these inserts: a, o1, b, o2, c
+        let a = wheel.insert("a", 10);
+        wheel.insert_oneshot("o1", 10);
+        let b = wheel.insert("b", 10);
+        wheel.insert_oneshot("o2", 10);
+        let c = wheel.insert("c", 10);
+
+        // Cancelling the neighbours of oneshot timers keeps the list intact
+        assert_eq!(wheel.cancel(b), Some("b"));
+        assert_eq!(wheel.cancel(a), Some("a"));
+        assert_eq!(wheel.dump_occupancy()[0][10], 3);
+
+        // Retain drops oneshot timers too
+        wheel.retain(|&task, _| task != "o1");
+        assert_eq!(wheel.cancel(c), Some("c"));
+
+        wheel.advance_to(11, &mut expired).unwrap();
+        assert_eq!(expired, vec!["o2"]);
+        assert!(wheel.is_empty());
+        assert_eq!(wheel.head(0, 10), None);
+    }
+
+    /// Deterministic pseudo-random numbers for the property tests: each call returns a
+    /// value below `bound`, from a 64-bit LCG started at `seed`
+    fn lcg(mut seed: u64) -> impl FnMut(u64) -> u64 {
+        move |bound| {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            (seed >> 33) % bound
+        }
+    }
+
+    /// Checks every bucket's head, tail and back links against a forward walk
+    fn assert_consistent_links<T>(wheel: &TimingWheel<T>) {
+        for bucket in 0..wheel.wheels.len() {
+            let mut prev = None;
+            let mut cursor = wheel.wheels[bucket];
+            while let Some(idx) = cursor {
+                let entry = wheel.slab.entry(idx).expect("bucket links a free slot");
+                assert_eq!(
+                    entry.prev, prev,
+                    "bad back link at {idx} in bucket {bucket}"
+                );
+                prev = Some(idx);
+                cursor = entry.next;
+            }
+            assert_eq!(wheel.tails[bucket], prev, "stale tail in bucket {bucket}");
+
+            let words = wheel.level_words();
+            let (level, slot) = (
+                bucket / wheel.config.wheel_size(),
+                bucket % wheel.config.wheel_size(),
+            );
+            let bit = wheel.occupied[level * words + slot / 64] >> (slot % 64) & 1;
+            assert_eq!(
+                bit == 1,
+                wheel.wheels[bucket].is_some(),
+                "stale bit for bucket {bucket}"
+            );
+        }
+
+        for (level, &count) in wheel.level_counts().iter().enumerate() {
+            let size = wheel.config.wheel_size();
+            let linked: usize = (level * size..(level + 1) * size)
+                .map(|bucket| {
+                    core::iter::successors(wheel.wheels[bucket], |&idx| {
+                        wheel.slab.entry(idx).and_then(|entry| entry.next)
+                    })
+                    .count()
+                })
+                .sum();
+            assert_eq!(count, linked, "stale count for level {level}");
+        }
+        let total: usize = wheel.level_counts().iter().sum();
+        assert_eq!(total + wheel.overflow_len(), wheel.len());
+    }
+
+    #[test]
+    fn test_occupancy_bitmap_tracks_every_bucket_change() {
+        // One word per level, several words per level, and less than a word per level
+        for config in [
+            WheelConfig::default(),
+            WheelConfig::new(8, 3),
+            WheelConfig::new(3, 5),
+        ] {
+            let mut wheel = TimingWheel::with_config(config);
+            let mut expired = Vec::new();
+            let mut keys = Vec::new();
+            for i in 0..400u64 {
+                keys.push(wheel.insert(i, (i * 7_919) % 20_000));
+            }
+            assert_consistent_links(&wheel);
+
+            for key in keys.iter().step_by(3) {
+                wheel.cancel(*key);
+            }
+            assert_consistent_links(&wheel);
+
+            // Skipping idle ticks relies on the bitmap; it must still match ticking
+            let mut naive = TimingWheel::with_config(config);
+            for (deadline, task) in wheel.iter().map(|(_, &t, d)| (d, t)).collect::<Vec<_>>() {
+                naive.insert(task, deadline);
+            }
+            let mut naive_expired = Vec::new();
+            for target in [1, 700, 5_000, 20_000] {
+                wheel.advance_to(target, &mut expired).unwrap();
+                while naive.current_time() < target {
+                    naive.tick(&mut naive_expired);
+                }
+                assert_consistent_links(&wheel);
+                expired.sort();
+                naive_expired.sort();
+                assert_eq!(expired, naive_expired);
+            }
+            assert!(wheel.is_empty());
+
+            for i in 0..50u64 {
+                wheel.insert(i, wheel.current_time() + i * 13);
+            }
+            wheel.retain(|&task, _| task % 2 == 0);
+            assert_consistent_links(&wheel);
+            wheel.clear_level(0);
+            assert_consistent_links(&wheel);
+            let len = wheel.len();
+            assert_eq!(wheel.drain().count(), len);
+            assert_consistent_links(&wheel);
+            assert_eq!(wheel.peek_next_deadline(), None);
+        }
+    }
+
+    #[test]
+    fn test_same_deadline_fires_in_insertion_order() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        // Inserted from level 2, then level 1, then straight into level 0 as time passes,
+        // so later inserts land in the slot before earlier ones cascade into it
+        let mut keys = Vec::new();
+        for i in 0..1_000u64 {
+            wheel.advance_to(i * 5, &mut expired).unwrap();
+            keys.push(wheel.insert(i, 5_000));
+        }
+        assert!(expired.is_empty());
+        assert_consistent_links(&wheel);
+
+        // Cancel the head, the tail and a few in between
+        for &i in &[0u64, 999, 1, 500, 501, 998] {
+            assert_eq!(wheel.cancel(keys[i as usize]), Some(i));
+            assert_consistent_links(&wheel);
+        }
+
+        wheel.advance_to(5_001, &mut expired).unwrap();
+        let expected: Vec<u64> = (0..1_000)
+            .filter(|i| ![0, 999, 1, 500, 501, 998].contains(i))
+            .collect();
+        assert_eq!(expired, expected);
+        assert_consistent_links(&wheel);
+    }
+
+    #[test]
+    fn test_fifo_links_survive_random_operations() {
+        let mut next = lcg(0xbb67_ae85_84ca_a73b_u64);
+
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        let mut keys = Vec::new();
+        for step in 0..3_000u64 {
+            match next(5) {
+                0 | 1 => keys.push(wheel.insert(step, wheel.current_time() + next(10_000))),
+                2 if !keys.is_empty() => {
+                    let key = keys.swap_remove(next(keys.len() as u64) as usize);
+                    wheel.cancel(key);
+                }
+                3 if !keys.is_empty() => {
+                    let key = keys[next(keys.len() as u64) as usize];
+                    wheel.reschedule(key, wheel.current_time() + next(500));
+                }
+                _ => wheel.tick_n(next(200), &mut expired),
+            }
+            if step % 50 == 0 {
+                assert_consistent_links(&wheel);
+            }
+        }
+        assert_consistent_links(&wheel);
+    }
+
+    #[test]
+    fn test_advance_to_rejects_backwards_target() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        wheel.insert("task", 5);
+        wheel.advance_to(10, &mut expired).unwrap();
+        assert_eq!(expired, vec!["task"]);
+
+        // Going backwards is an error and leaves the clock alone
+        let err = wheel.advance_to(4, &mut expired).unwrap_err();
+        assert_eq!(err, ClockWentBackwards { by: 6 });
+        assert_eq!(wheel.current_time(), 10);
+
+        // Advancing to "now" is a no-op
+        assert!(wheel.advance_to(10, &mut expired).is_ok());
+        assert_eq!(wheel.current_time(), 10);
+    }
+
+    #[test]
+    fn test_clock_step_back_mid_run() {
+        // 1 tick = 1ms, so a 30 second NTP step back is 30_000 ticks
+        const STEP_BACK: u64 = 30_000;
+
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        let deadlines: Vec<u64> = (1..=200).map(|i| i * 500).collect();
+        for &deadline in &deadlines {
+            wheel.insert(deadline, deadline);
+        }
+
+        // Wall clock readings: steady progress, a 30s step back, then steady again
+        let mut readings: Vec<u64> = (1..=100).map(|i| i * 400).collect();
+        let before_step = *readings.last().unwrap();
+        readings.extend((0..300).map(|i| before_step - STEP_BACK + i * 400));
+
+        let mut rejected = 0;
+        let mut high_water = 0;
+        for now in readings {
+            match wheel.advance_to(now, &mut expired) {
+                Ok(()) => assert_eq!(wheel.current_time(), now),
+                Err(err) => {
+                    rejected += 1;
+                    assert_eq!(err.by, wheel.current_time() - now);
+                }
+            }
+            // The tick counter is never rewound
+            assert!(wheel.current_time() >= high_water);
+            high_water = wheel.current_time();
+        }
+        assert!(rejected > 0);
+
+        // Every timer fires exactly once: nothing is lost or duplicated
+        let mut fired = expired.clone();
+        fired.sort();
+        assert_eq!(fired, deadlines);
+    }
+
+    #[cfg(feature = "debug-events")]
+    #[test]
+    fn test_recent_events_records_sequence() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        let near = wheel.insert("near", 1);
+        let far = wheel.insert("far", 65);
+        let gone = wheel.insert("gone", 2);
+        wheel.cancel(gone);
+        wheel.tick_to_with(2, |_| {}, &mut expired).unwrap();
+
+        assert_eq!(
+            wheel.recent_events(),
+            &[
+                WheelEvent::Insert {
+                    handle: near,
+                    deadline: 1,
+                    level: 0
+                },
+                WheelEvent::Insert {
+                    handle: far,
+                    deadline: 65,
+                    level: 1
+                },
+                WheelEvent::Insert {
+                    handle: gone,
+                    deadline: 2,
+                    level: 0
+                },
+                WheelEvent::Cancel { handle: gone },
+                WheelEvent::Tick { tick: 0 },
+                WheelEvent::Tick { tick: 1 },
+                WheelEvent::Expire {
+                    handle: near,
+                    deadline: 1
+                },
+            ]
+        );
+
+        // Crossing the level 1 boundary cascades "far" down without changing its handle
+        wheel.tick_to_with(65, |_| {}, &mut expired).unwrap();
+        let events = wheel.recent_events();
+        assert_eq!(
+            &events[events.len() - 3..],
+            &[
+                WheelEvent::Tick { tick: 63 },
+                WheelEvent::Cascade {
+                    handle: far,
+                    deadline: 65,
+                    from_level: 1,
+                    to_level: 0,
+                },
+                WheelEvent::Tick { tick: 64 },
+            ]
+        );
+    }
+
+    #[cfg(feature = "debug-events")]
+    #[test]
+    fn test_recent_events_keeps_only_the_tail() {
+        let mut wheel: TimingWheel<()> = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        wheel.tick_to_with(10_000, |_| {}, &mut expired).unwrap();
+
+        let events = wheel.recent_events();
+        assert_eq!(events.len(), crate::events::EVENT_LOG_CAPACITY);
+        assert_eq!(events.last(), Some(&WheelEvent::Tick { tick: 9_999 }));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_duration_api_rounds_up_to_ticks() {
+        let mut wheel = TimingWheel::new_with_resolution(Duration::from_millis(10));
+        let mut expired = Vec::new();
+        assert_eq!(wheel.resolution(), Duration::from_millis(10));
+
+        // 25ms at 10ms/tick rounds up to 3 ticks
+        wheel.insert_after("after", Duration::from_millis(25));
+        // Exactly 5 ticks after the origin
+        let when = wheel.tick_to_instant(5);
+        wheel.insert_at("at", when);
+
+        wheel.advance_to(3, &mut expired).unwrap();
+        assert!(expired.is_empty());
+
+        wheel.tick(&mut expired);
+        assert_eq!(expired, vec!["after"]);
+
+        wheel.advance_to(6, &mut expired).unwrap();
+        assert_eq!(expired, vec!["after", "at"]);
+    }
+
+    #[test]
+    fn test_insert_after_is_relative_to_current_tick() {
+        let mut wheel = TimingWheel::new_with_resolution(Duration::from_millis(1));
+        let mut expired = Vec::new();
+
+        wheel.advance_to(100, &mut expired).unwrap();
+        wheel.insert_after("task", Duration::from_micros(1500));
+
+        // 1.5ms rounds up to 2 ticks past tick 100
+        wheel.advance_to(102, &mut expired).unwrap();
+        assert!(expired.is_empty());
+        wheel.tick(&mut expired);
+        assert_eq!(expired, vec!["task"]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_tick_to_instant_round_trips() {
+        let wheel: TimingWheel<()> = TimingWheel::new_with_resolution(Duration::from_micros(250));
+        let origin = wheel.tick_to_instant(0);
+
+        assert_eq!(wheel.tick_to_instant(4) - origin, Duration::from_millis(1));
+        assert_eq!(
+            wheel.tick_to_instant(4_000_000) - origin,
+            Duration::from_secs(1000)
+        );
+    }
+
+    #[test]
+    fn test_clear_drops_every_pending_timer_once() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        struct DropCounter(Rc<Cell<usize>>);
+        impl Drop for DropCounter {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Rc::new(Cell::new(0));
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        // Spread timers across all four levels
+        for deadline in [5, 100, 5_000, 300_000, 10_000_000] {
+            wheel.insert(DropCounter(drops.clone()), deadline);
+        }
+        wheel.advance_to(10, &mut expired).unwrap();
+        assert_eq!(expired.len(), 1);
+        drop(expired.drain(..));
+        assert_eq!(drops.get(), 1);
+
+        wheel.clear();
+        assert_eq!(drops.get(), 5);
+        assert_eq!(wheel.current_time(), 0);
+
+        // Nothing left to fire, and the wheel is still usable
+        wheel.insert(DropCounter(drops.clone()), 20);
+        wheel.advance_to(20_000_000, &mut expired).unwrap();
+        assert_eq!(expired.len(), 1);
+        drop(expired);
+        drop(wheel);
+        assert_eq!(drops.get(), 6);
+    }
+
+    #[test]
+    fn test_drain_returns_every_pending_timer_once() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        let deadlines = [3, 64, 100, 4_096, 5_000, 262_144, 300_000];
+        for (i, &deadline) in deadlines.iter().enumerate() {
+            wheel.insert(i, deadline);
+        }
+        let cancelled = wheel.insert(99, 50);
+        wheel.cancel(cancelled);
+
+        // Fire the first one and cascade a couple of others before draining
+        wheel.advance_to(70, &mut expired).unwrap();
+        assert_eq!(expired, vec![0, 1]);
+
+        let mut drained: Vec<(u64, usize)> = wheel.drain().collect();
+        drained.sort();
+        assert_eq!(
+            drained,
+            vec![(100, 2), (4_096, 3), (5_000, 4), (262_144, 5), (300_000, 6)]
+        );
+
+        // Nothing fires afterwards, and the wheel is reusable
+        wheel.advance_to(400_000, &mut expired).unwrap();
+        assert_eq!(expired.len(), 2);
+
+        wheel.insert(7, 400_010);
+        wheel.advance_to(400_011, &mut expired).unwrap();
+        assert_eq!(expired.last(), Some(&7));
+    }
+
+    #[test]
+    fn test_deadline_beyond_horizon_fires_on_time() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        // 64^4 + 5 does not fit in the four levels
+        let deadline = HORIZON + 5;
+        wheel.insert("overflow", deadline);
+        wheel.insert("edge", HORIZON - 1);
+        assert_eq!(wheel.overflow.len(), 1);
+
+        wheel.advance_to(HORIZON, &mut expired).unwrap();
+        assert_eq!(expired, vec!["edge"]);
+        // Promoted into the wheels once the hierarchy wrapped
+        assert!(wheel.overflow.is_empty());
+
+        wheel.advance_to(deadline, &mut expired).unwrap();
+        assert_eq!(expired.len(), 1);
+        wheel.tick(&mut expired);
+        assert_eq!(expired, vec!["edge", "overflow"]);
+    }
+
+    #[test]
+    fn test_cancel_overflow_timer() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        let far = wheel.insert("far", 3 * HORIZON);
+        let near = wheel.insert("near", 10);
+        // Parked timers report their deadline like wheel-resident ones
+        assert_eq!(wheel.deadline_of(far), Some(3 * HORIZON));
+        assert_eq!(wheel.cancel(far), Some("far"));
+        assert_eq!(wheel.deadline_of(far), None);
+        assert!(wheel.overflow.is_empty());
+
+        wheel.advance_to(11, &mut expired).unwrap();
+        assert_eq!(expired, vec!["near"]);
+        assert_eq!(wheel.cancel(near), None);
+    }
+
+    #[test]
+    fn test_tick_to_with_calls_back_once_per_tick() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        wheel.insert("task", 70);
+
+        let mut seen = Vec::new();
+        wheel
+            .tick_to_with(100, |tick| seen.push(tick), &mut expired)
+            .unwrap();
+
+        assert_eq!(seen.len(), 100);
+        assert_eq!(seen, (1..=100).collect::<Vec<u64>>());
+        assert_eq!(expired, vec!["task"]);
+
+        // Rejected targets don't invoke the callback at all
+        let mut calls = 0;
+        assert!(
+            wheel
+                .tick_to_with(50, |_| calls += 1, &mut expired)
+                .is_err()
+        );
+        assert_eq!(calls, 0);
+    }
+
+    #[test]
+    fn test_stale_handle_cannot_cancel_reused_slot() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        let old = wheel.insert("old", 1);
+        wheel.advance_to(2, &mut expired).unwrap();
+        assert_eq!(expired, vec!["old"]);
+
+        // The freed slot is handed straight to the next insert
+        let new = wheel.insert("new", 10);
+        assert_eq!(new.index(), old.index());
+
+        assert_eq!(wheel.cancel(old), None);
+        wheel.advance_to(11, &mut expired).unwrap();
+        assert_eq!(expired, vec!["old", "new"]);
+    }
+
+    #[test]
+    fn test_insert_with_hands_the_task_its_own_handle() {
+        struct Connection {
+            id: u32,
+            idle_timer: Key,
+        }
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        let connect = |wheel: &mut TimingWheel<Connection>, id, deadline| {
+            wheel.insert_with(deadline, |idle_timer| Connection { id, idle_timer })
+        };
+
+        // A fresh slot, then a slot freed by a cancel
+        let first = connect(&mut wheel, 1, 10);
+        assert_eq!(wheel.get(first).unwrap().idle_timer, first);
+        let closed = connect(&mut wheel, 2, 10);
+        assert_eq!(
+            wheel.cancel(closed).map(|conn| conn.idle_timer),
+            Some(closed)
+        );
+        let reused = connect(&mut wheel, 3, 30);
+        assert_eq!(reused.index(), closed.index());
+        assert_ne!(reused, closed);
+
+        // The handle the task carries is a working one
+        let carried = wheel.get(reused).unwrap().idle_timer;
+        assert_eq!(carried, reused);
+        assert_eq!(wheel.cancel(carried).map(|conn| conn.id), Some(3));
+
+        wheel.advance_to(11, &mut expired).unwrap();
+        let fired: Vec<_> = expired
+            .iter()
+            .map(|conn| (conn.id, conn.idle_timer))
+            .collect();
+        assert_eq!(fired, vec![(1, first)]);
+    }
+
+    #[test]
+    fn test_stale_handle_cannot_reach_reused_slot_through_any_api() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        // Each round frees the slot one way or the other and hands it to the next timer
+        let mut stale = Vec::new();
+        let mut current = wheel.insert(0u32, 5);
+        for round in 1..=20u32 {
+            if round % 2 == 0 {
+                wheel.cancel(current);
+            } else {
+                wheel
+                    .advance_to(wheel.current_time() + 10, &mut expired)
+                    .unwrap();
+            }
+            stale.push(current);
+            current = wheel.insert(round, wheel.current_time() + 5);
+            assert_eq!(current.index(), stale[0].index());
+        }
+
+        for &old in &stale {
+            assert_eq!(wheel.get(old), None);
+            assert_eq!(wheel.get_mut(old), None);
+            assert_eq!(wheel.reschedule(old, 1_000), None);
+            assert_eq!(wheel.touch(old, 1_000), None);
+            assert_eq!(wheel.cancel(old), None);
+        }
+        // None of that moved or freed the live timer sharing the slot
+        let now = wheel.current_time();
+        assert_eq!(wheel.deadline_of(current), Some(now + 5));
+        wheel.advance_to(now + 6, &mut expired).unwrap();
+        assert_eq!(expired.last(), Some(&20));
+    }
+
+    #[test]
+    fn test_get_and_get_mut_task_by_handle() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        let id = wheel.insert(vec!["request"], 5);
+        wheel.get_mut(id).unwrap().push("retry");
+        assert_eq!(wheel.get(id), Some(&vec!["request", "retry"]));
+
+        // Still reachable after cascading from level 1
+        let far = wheel.insert(vec!["far"], 100);
+        wheel.advance_to(70, &mut expired).unwrap();
+        assert_eq!(wheel.get(far), Some(&vec!["far"]));
+
+        // Expired handles stop resolving, even once the slot is reused
+        assert!(wheel.get(id).is_none());
+        let reused = wheel.insert(vec!["new"], 80);
+        assert_eq!(reused.index(), id.index());
+        assert!(wheel.get(id).is_none());
+        assert!(wheel.get_mut(id).is_none());
+
+        wheel.cancel(far);
+        assert!(wheel.get(far).is_none());
+    }
+
+    #[test]
+    fn test_state_mutated_by_handle_is_what_expires() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        let id = wheel.insert(("conn", 0u32), 200);
+        for _ in 0..3 {
+            wheel.get_mut(id).unwrap().1 += 1;
+        }
+        assert_eq!(wheel.get(id), Some(&("conn", 3)));
+
+        wheel.advance_to(201, &mut expired).unwrap();
+        assert_eq!(expired, vec![("conn", 3)]);
+    }
+
+    /// Observer that records every callback, shared with the test through an `Arc`
+    #[derive(Clone, Default)]
+    struct RecordingObserver(std::sync::Arc<std::sync::Mutex<Vec<String>>>);
+
+    impl RecordingObserver {
+        fn events(&self) -> Vec<String> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl WheelObserver for RecordingObserver {
+        fn on_reschedule(&mut self, id: Key, old_deadline: u64, new_deadline: u64) {
+            self.0.lock().unwrap().push(format!(
+                "reschedule {} {old_deadline}->{new_deadline}",
+                id.index()
+            ));
+        }
+
+        fn on_bulk_reschedule(&mut self, count: usize) {
+            self.0.lock().unwrap().push(format!("bulk {count}"));
+        }
+
+        fn on_overflow_parked(&mut self, id: Key) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("parked {}", id.index()));
+        }
+
+        fn on_slab_shrink(&mut self, old_capacity: usize, new_capacity: usize) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("shrink {old_capacity}->{new_capacity}"));
+        }
+    }
+
+    #[test]
+    fn test_observer_sees_overflow_parking() {
+        let observer = RecordingObserver::default();
+        let mut wheel = TimingWheel::new();
+        wheel.set_observer(observer.clone());
+        let mut expired = Vec::new();
+
+        // In-range inserts, cancels and ticks are silent
+        let near = wheel.insert("near", 10);
+        wheel.cancel(near);
+        wheel.advance_to(20, &mut expired).unwrap();
+        assert!(observer.events().is_empty());
+
+        let far = wheel.insert("far", HORIZON + 100);
+        wheel.insert("edge", HORIZON - 1);
+        assert_eq!(observer.events(), vec![format!("parked {}", far.index())]);
+
+        // Promotion back into the wheels is not reported
+        wheel.advance_to(HORIZON + 101, &mut expired).unwrap();
+        assert_eq!(expired, vec!["edge", "far"]);
+        assert_eq!(observer.events().len(), 1);
+
+        assert!(wheel.take_observer().is_some());
+        wheel.insert("unobserved", 3 * HORIZON);
+        assert_eq!(observer.events().len(), 1);
+    }
+
+    #[test]
+    fn test_debug_works_for_non_debug_tasks() {
+        struct NotDebug;
+
+        let mut wheel = TimingWheel::new();
+        wheel.insert(NotDebug, 7);
+        wheel.insert(NotDebug, HORIZON * 2);
+
+        let dump = format!("{wheel:?}");
+        assert!(dump.starts_with("TimingWheel {"));
+        assert!(dump.contains("current_tick: 0"));
+        assert!(dump.contains("deadline: 7"));
+        assert!(dump.contains("NotDebug"));
+    }
+
+    #[test]
+    fn test_contains_and_deadline_of() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        let cancelled = wheel.insert("cancelled", 42);
+        let fired = wheel.insert("fired", 3);
+        let parked = wheel.insert("parked", 5_000);
+        assert!(wheel.contains(cancelled));
+        assert_eq!(wheel.deadline_of(cancelled), Some(42));
+        assert_eq!(wheel.deadline_of(parked), Some(5_000));
+
+        wheel.cancel(cancelled);
+        assert!(!wheel.contains(cancelled));
+        assert_eq!(wheel.deadline_of(cancelled), None);
+
+        wheel.advance_to(4, &mut expired).unwrap();
+        assert!(!wheel.contains(fired));
+        assert_eq!(wheel.deadline_of(fired), None);
+
+        // Both freed slots get reused; the old handles must stay dead
+        let reused_a = wheel.insert("a", 10);
+        let reused_b = wheel.insert("b", 11);
+        let mut reused = [reused_a.index(), reused_b.index()];
+        reused.sort();
+        let mut old = [cancelled.index(), fired.index()];
+        old.sort();
+        assert_eq!(reused, old);
+        assert!(!wheel.contains(cancelled));
+        assert!(!wheel.contains(fired));
+        assert_eq!(wheel.deadline_of(fired), None);
+        assert_eq!(wheel.deadline_of(reused_a), Some(10));
+
+        // A handle that was never issued by this wheel
+        let bogus = Key::new(fired.owner(), NonZeroU32::new(500).unwrap(), 0);
+        assert!(!wheel.contains(bogus));
+        assert_eq!(wheel.deadline_of(bogus), None);
+
+        // Cascading does not change what the handle reports
+        wheel.advance_to(4_500, &mut expired).unwrap();
+        assert!(wheel.contains(parked));
+        assert_eq!(wheel.deadline_of(parked), Some(5_000));
+    }
+
+    #[test]
+    fn test_try_cancel_tells_stale_from_invalid_handles() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        let live = wheel.insert("live", 50);
+        let fired = wheel.insert("fired", 3);
+        assert_eq!(wheel.try_cancel(live), Ok("live"));
+        assert_eq!(wheel.try_cancel(live), Err(CancelError::AlreadyCompleted));
+
+        wheel.advance_to(4, &mut expired).unwrap();
+        assert_eq!(wheel.try_cancel(fired), Err(CancelError::AlreadyCompleted));
+
+        // Still stale after the slot is reused by another timer
+        let reused = wheel.insert("reused", 9);
+        assert_eq!(reused.index(), fired.index());
+        assert_eq!(wheel.try_cancel(fired), Err(CancelError::AlreadyCompleted));
+        assert_eq!(wheel.try_cancel(reused), Ok("reused"));
+
+        // Fabricated handles: past the slab, or from a generation not issued yet
+        let past_end = Key::new(live.owner(), NonZeroU32::new(500).unwrap(), 0);
+        assert_eq!(wheel.try_cancel(past_end), Err(CancelError::InvalidHandle));
+        let ahead = Key::new(live.owner(), live.index(), live.generation() + 100);
+        assert_eq!(wheel.try_cancel(ahead), Err(CancelError::InvalidHandle));
+    }
+
+    #[test]
+    fn test_every_failure_converts_into_wheel_error() {
+        fn schedule_and_cancel(
+            wheel: &mut TimingWheel<&'static str>,
+            task: &'static str,
+            deadline: u64,
+            key: Key,
+        ) -> Result<Key, WheelError> {
+            let inserted = wheel.try_insert(task, deadline)?;
+            wheel.try_cancel(key)?;
+            Ok(inserted)
+        }
+
+        let mut wheel = TimingWheel::builder()
+            .fire_policy(FirePolicy::Reject)
+            .max_timers(2)
+            .build();
+        wheel.advance_to(10, &mut Vec::new()).unwrap();
+        let live = wheel.insert("live", 20);
+
+        // The task comes back alongside the error
+        let (err, task) = wheel.try_insert("late", 5).unwrap_err().into_parts();
+        assert_eq!((err, task), (WheelError::DeadlineInPast, "late"));
+        assert_eq!(err.to_string(), "deadline is before the current tick");
+        assert_eq!(
+            schedule_and_cancel(&mut wheel, "late", 5, live),
+            Err(WheelError::DeadlineInPast)
+        );
+        assert!(wheel.contains(live));
+
+        let foreign = TimingWheel::<()>::new().insert((), 1);
+        assert_eq!(
+            schedule_and_cancel(&mut wheel, "a", 30, foreign),
+            Err(WheelError::InvalidHandle)
+        );
+        assert_eq!(
+            schedule_and_cancel(&mut wheel, "b", 30, live),
+            Err(WheelError::Full)
+        );
+
+        // Make room, then cancel `live` twice
+        let a = wheel.iter().find(|&(_, &task, _)| task == "a").unwrap().0;
+        wheel.cancel(a);
+        assert!(schedule_and_cancel(&mut wheel, "c", 40, live).is_ok());
+        assert_eq!(
+            schedule_and_cancel(&mut wheel, "d", 40, live),
+            Err(WheelError::AlreadyCompleted)
+        );
+        assert_eq!(wheel.len(), 2);
+        assert_eq!(
+            WheelError::Full.to_string(),
+            "wheel holds its maximum number of timers"
+        );
+        // The specific errors read the same as what they convert into
+        assert_eq!(
+            InsertError::Full("e").to_string(),
+            WheelError::Full.to_string()
+        );
+        assert_eq!(
+            CancelError::AlreadyCompleted.to_string(),
+            WheelError::AlreadyCompleted.to_string()
+        );
+    }
+
+    #[test]
+    fn test_handles_from_another_wheel_are_rejected() {
+        let mut a = TimingWheel::new();
+        let mut b = TimingWheel::new();
+        let from_a = a.insert("a", 10);
+        // Same slot and generation in both wheels, only the owner differs
+        let from_b = b.insert("b", 20);
+        assert_eq!(
+            (from_a.index(), from_a.generation()),
+            (from_b.index(), from_b.generation())
+        );
+        assert_ne!(from_a.owner(), from_b.owner());
+
+        assert!(!b.contains(from_a));
+        assert_eq!(b.deadline_of(from_a), None);
+        assert_eq!(b.reschedule(from_a, 5), None);
+        assert_eq!(b.try_cancel(from_a), Err(CancelError::InvalidHandle));
+        assert_eq!(b.deadline_of(from_b), Some(20));
+
+        // The raw parts round-trip, and only for the wheel that issued them
+        let raw = Key::from_raw(from_b.owner(), from_b.index(), from_b.generation());
+        assert_eq!(raw, from_b);
+        assert_eq!(a.cancel(raw), None);
+        assert_eq!(b.cancel(raw), Some("b"));
+        assert_eq!(a.cancel(from_a), Some("a"));
+    }
+
+    #[test]
+    fn test_try_cancel_after_shrink_still_reports_completed() {
+        let mut wheel = TimingWheel::new();
+        let keys: Vec<_> = (0..5_000u64).map(|i| wheel.insert(i, 1 + i % 50)).collect();
+        wheel.advance_to(100, &mut Vec::new()).unwrap();
+        wheel.shrink_to_fit();
+
+        // The slot behind this handle no longer exists at all
+        let last = *keys.last().unwrap();
+        assert_eq!(wheel.try_cancel(last), Err(CancelError::AlreadyCompleted));
+    }
+
+    #[test]
+    fn test_contains_after_bulk_removal() {
+        let mut wheel = TimingWheel::new();
+        let kept = wheel.insert("kept", 10);
+        let dropped = wheel.insert("dropped", 10);
+        let far = wheel.insert("far", HORIZON + 10);
+
+        wheel.retain(|&task, _| task != "dropped");
+        assert!(wheel.contains(kept) && wheel.contains(far));
+        assert!(!wheel.contains(dropped));
+
+        assert_eq!(wheel.drain().count(), 2);
+        assert!(!wheel.contains(kept) && !wheel.contains(far));
+
+        // Handles issued before a clear never resolve again, even once slots refill
+        let before = wheel.insert("before", 5);
+        wheel.clear();
+        let after = wheel.insert("after", 5);
+        assert_eq!(after.index(), before.index());
+        assert!(!wheel.contains(before));
+        assert!(wheel.contains(after));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_wait_next_with_mock_clock_is_exact() {
+        use crate::clock::MockClock;
+
+        let mut wheel = TimingWheel::new_with_resolution(Duration::from_millis(1));
+        let clock = MockClock::new(wheel.tick_to_instant(0));
+
+        // Nothing pending: return right away without moving time
+        assert_eq!(wheel.wait_next_with(&clock), (0, Vec::new()));
+        assert_eq!(clock.now(), wheel.tick_to_instant(0));
+
+        wheel.insert_after("b", Duration::from_millis(80));
+        wheel.insert_after("a", Duration::from_millis(50));
+        wheel.insert_after("a2", Duration::from_millis(50));
+
+        let (tick, mut fired) = wheel.wait_next_with(&clock);
+        fired.sort();
+        assert_eq!((tick, fired), (50, vec!["a", "a2"]));
+        assert_eq!(clock.now(), wheel.tick_to_instant(50));
+
+        assert_eq!(wheel.wait_next_with(&clock), (80, vec!["b"]));
+        assert_eq!(clock.now(), wheel.tick_to_instant(80));
+        assert_eq!(wheel.current_time(), 81);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_wait_next_reanchors_when_clock_steps_backwards() {
+        use crate::clock::MockClock;
+
+        let mut wheel = TimingWheel::new_with_resolution(Duration::from_millis(1));
+        let clock = MockClock::new(wheel.tick_to_instant(0));
+        wheel.insert_after("a", Duration::from_secs(10));
+        wheel.insert_after("b", Duration::from_secs(20));
+        wheel.insert_after("c", Duration::from_secs(60));
+
+        assert_eq!(wheel.wait_next_with(&clock), (10_000, vec!["a"]));
+
+        // NTP steps the wall clock back 30 seconds mid-run
+        let stepped = clock.now() - Duration::from_secs(30);
+        clock.set(stepped);
+
+        // The tick counter does not rewind, and "b" still waits out its remaining ticks
+        assert_eq!(wheel.wait_next_with(&clock), (20_000, vec!["b"]));
+        assert_eq!(
+            clock.now() - stepped,
+            Duration::from_millis(20_000 - 10_001)
+        );
+        assert_eq!(wheel.tick_to_instant(20_000), clock.now());
+
+        assert_eq!(wheel.wait_next_with(&clock), (60_000, vec!["c"]));
+        assert!(wheel.is_empty());
+        assert_eq!(wheel.wait_next_with(&clock), (60_001, Vec::new()));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_wait_next_on_an_unrepresentable_deadline_waits_and_returns() {
+        use crate::clock::MockClock;
+
+        let mut wheel = TimingWheel::new_with_resolution(Duration::from_secs(1));
+        let clock = MockClock::new(wheel.tick_to_instant(0));
+        // Saturated to `u64::MAX` seconds, far beyond what an `Instant` can hold
+        let far = wheel.insert_in("far", u64::MAX);
+        assert_eq!(wheel.checked_tick_to_instant(u64::MAX), None);
+
+        assert_eq!(wheel.wait_next_with(&clock), (0, Vec::new()));
+        assert_eq!(clock.now(), wheel.tick_to_instant(0) + MAX_WAIT);
+        assert!(wheel.contains(far));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_wait_next_sleeps_until_deadline() {
+        let mut wheel = TimingWheel::new_with_resolution(Duration::from_millis(1));
+        let start = Instant::now();
+        wheel.insert_after("timeout", Duration::from_millis(20));
+
+        let (tick, fired) = wheel.wait_next();
+        let elapsed = start.elapsed();
+
+        assert_eq!((tick, fired), (20, vec!["timeout"]));
+        assert!(elapsed >= Duration::from_millis(20));
+        // Generous upper bound for loaded CI machines
+        assert!(elapsed < Duration::from_secs(2), "slept {elapsed:?}");
+    }
+
+    #[test]
+    fn test_overflow_promotion_is_amortized() {
+        const BUDGET: usize = 8;
+        const PARKED: u64 = 2_000;
+
+        let mut wheel = TimingWheel::new();
+        wheel.set_promotion_budget(BUDGET);
+        // Everything comes in range within the same handful of ticks
+        for i in 0..PARKED {
+            let deadline = HORIZON + 10 + (i % 50);
+            wheel.insert(deadline, deadline);
+        }
+        assert_eq!(wheel.overflow_len(), PARKED as usize);
+
+        let mut expired = Vec::new();
+        while wheel.overflow_len() > 0 {
+            let before = wheel.overflow_len();
+            wheel.tick(&mut expired);
+            assert!(before - wheel.overflow_len() <= BUDGET);
+        }
+        assert!(expired.is_empty());
+
+        // Nothing promoted lazily may fire late (or early)
+        wheel.advance_to(HORIZON + 10, &mut expired).unwrap();
+        assert!(expired.is_empty());
+        let mut fired = 0;
+        while wheel.current_time() < HORIZON + 60 {
+            let tick = wheel.current_time();
+            wheel.tick(&mut expired);
+            fired += expired.len();
+            assert!(expired.drain(..).all(|deadline| deadline == tick));
+        }
+        assert_eq!(fired as u64, PARKED);
+    }
+
+    #[test]
+    fn test_zero_promotion_budget_still_fires_on_time() {
+        let mut wheel = TimingWheel::new();
+        wheel.set_promotion_budget(0);
+        for _ in 0..100 {
+            wheel.insert("far", HORIZON + 5);
+        }
+
+        let mut expired = Vec::new();
+        wheel.advance_to(HORIZON + 5, &mut expired).unwrap();
+        assert!(expired.is_empty());
+        assert_eq!(wheel.overflow_len(), 0);
+
+        wheel.tick(&mut expired);
+        assert_eq!(expired.len(), 100);
+    }
+
+    #[test]
+    fn test_reschedule_keeps_handle() {
+        let mut wheel = TimingWheel::new();
+        let key = wheel.insert("idle", 10);
+        wheel.insert("other", 10);
+
+        // Push it out across levels, then pull it back in
+        assert_eq!(wheel.reschedule(key, 5_000), Some(key));
+        assert_eq!(wheel.deadline_of(key), Some(5_000));
+        assert_eq!(wheel.reschedule(key, 20), Some(key));
+
+        let mut expired = Vec::new();
+        wheel.advance_to(11, &mut expired).unwrap();
+        assert_eq!(expired, vec!["other"]);
+        assert!(wheel.contains(key));
+
+        wheel.advance_to(21, &mut expired).unwrap();
+        assert_eq!(expired, vec!["other", "idle"]);
+        assert_eq!(wheel.reschedule(key, 30), None, "fired handles are stale");
+    }
+
+    #[test]
+    fn test_dropped_guard_cancels_its_timer() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        let key = {
+            let mut guard = wheel.insert_guard("timeout", 10);
+            guard.wheel().advance_to(5, &mut expired).unwrap();
+            assert!(guard.is_armed());
+            guard.id()
+        };
+        assert!(!wheel.contains(key));
+        wheel.advance_to(20, &mut expired).unwrap();
+        assert!(expired.is_empty());
+    }
+
+    #[test]
+    fn test_forgotten_guard_fires_normally() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        let key = wheel.insert_guard("timeout", 10).forget();
+        assert_eq!(wheel.deadline_of(key), Some(10));
+        wheel.advance_to(11, &mut expired).unwrap();
+        assert_eq!(expired, vec!["timeout"]);
+
+        // Firing inside the scope disarms the guard; dropping it then touches nothing
+        let mut guard = wheel.insert_guard("inner", 12);
+        guard.wheel().advance_to(13, &mut expired).unwrap();
+        assert!(!guard.is_armed());
+        let reused = guard.wheel().insert("reused", 50);
+        assert_eq!(reused.index(), guard.id().index());
+        drop(guard);
+        assert!(wheel.contains(reused));
+        assert_eq!(expired, vec!["timeout", "inner"]);
+    }
+
+    #[test]
+    fn test_insert_in_the_past_fires_next_tick() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        wheel.advance_to(10, &mut expired).unwrap();
+
+        // Slot 3 was already passed this rotation; the timer must not wait for it
+        wheel.insert("late", 3);
+        wheel.insert("later", 0);
+        assert_eq!(wheel.peek_next_deadline(), Some(0));
+        wheel.tick(&mut expired);
+        assert_eq!(expired, vec!["late", "later"]);
+        assert_eq!(wheel.current_time(), 11);
+
+        // Same once the wheel is well into a later rotation of level 0
+        wheel.advance_to(200, &mut expired).unwrap();
+        wheel.insert("stale", 130);
+        wheel.tick(&mut expired);
+        assert_eq!(expired, vec!["late", "later", "stale"]);
+    }
+
+    #[test]
+    fn test_fire_policy_for_past_deadlines() {
+        let mut fire = TimingWheel::new();
+        let mut reject = TimingWheel::builder()
+            .fire_policy(FirePolicy::Reject)
+            .build();
+        let mut expired = Vec::new();
+        fire.advance_to(100, &mut expired).unwrap();
+        reject.advance_to(100, &mut expired).unwrap();
+
+        for (task, deadline) in [("equal", 100), ("one less", 99), ("far less", 3)] {
+            fire.try_insert(task, deadline).unwrap();
+        }
+        // All fire on the very next tick, in insertion order
+        fire.tick(&mut expired);
+        assert_eq!(expired, vec!["equal", "one less", "far less"]);
+        assert_eq!(fire.current_time(), 101);
+
+        expired.clear();
+        reject.try_insert("equal", 100).unwrap();
+        assert_eq!(
+            reject.try_insert("one less", 99),
+            Err(InsertError::DeadlineInPast("one less"))
+        );
+        let refused = reject.try_insert("far less", 3).unwrap_err();
+        assert_eq!(refused.into_task(), "far less");
+        assert_eq!(reject.len(), 1);
+        reject.tick(&mut expired);
+        assert_eq!(expired, vec!["equal"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "deadline 99 is before the current tick 100")]
+    fn test_reject_policy_makes_insert_panic() {
+        let mut wheel = TimingWheel::builder()
+            .fire_policy(FirePolicy::Reject)
+            .build();
+        wheel.advance_to(100, &mut Vec::new()).unwrap();
+        wheel.insert("late", 99);
+    }
+
+    #[test]
+    fn test_max_timers_applies_backpressure_until_timers_leave() {
+        let mut wheel = TimingWheel::with_max_timers(10);
+        let mut expired = Vec::new();
+
+        let keys: Vec<_> = (0..10u64)
+            .map(|i| wheel.try_insert(i, 1 + i).unwrap())
+            .collect();
+        assert!(wheel.is_full());
+        assert_eq!(wheel.try_insert(99, 5), Err(InsertError::Full(99)));
+        assert_eq!(wheel.len(), 10);
+
+        // Three expire and one is cancelled: exactly four inserts fit again
+        wheel.advance_to(4, &mut expired).unwrap();
+        assert_eq!(expired, vec![0, 1, 2]);
+        wheel.cancel(keys[9]);
+        for i in 0..4 {
+            assert!(wheel.try_insert(100 + i, 50).is_ok());
+        }
+        assert_eq!(wheel.try_insert(104, 50).unwrap_err().into_task(), 104);
+
+        // Moving a timer doesn't take up room
+        assert!(wheel.reschedule(keys[5], 60).is_some());
+        assert!(wheel.is_full());
+    }
+
+    #[test]
+    #[should_panic(expected = "wheel is full")]
+    fn test_insert_into_full_wheel_panics() {
+        let mut wheel = TimingWheel::builder().max_timers(1).build();
+        wheel.insert("first", 5);
+        wheel.insert("second", 5);
+    }
+
+    #[test]
+    fn test_reschedule_into_the_past_fires_next_tick() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        wheel.advance_to(100, &mut expired).unwrap();
+
+        let key = wheel.insert("late", 500);
+        assert!(wheel.reschedule(key, 3).is_some());
+        assert_eq!(wheel.deadline_of(key), Some(100));
+
+        wheel.tick(&mut expired);
+        assert_eq!(expired, vec!["late"]);
+    }
+
+    #[test]
+    fn test_reschedule_notifies_observer() {
+        let observer = RecordingObserver::default();
+        let mut wheel = TimingWheel::new();
+        wheel.set_observer(observer.clone());
+
+        let key = wheel.insert("t", 10);
+        assert!(wheel.reschedule(key, 40).is_some());
+        let bogus = Key::new(key.owner(), NonZeroU32::new(99).unwrap(), 0);
+        assert_eq!(wheel.reschedule(bogus, 40), None);
+
+        assert_eq!(
+            observer.events(),
+            vec![format!("reschedule {} 10->40", key.index())]
+        );
+    }
+
+    #[test]
+    fn test_stats_count_a_known_workload() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        // 10 on level 0, 5 that cascade once (level 1), 2 that cascade twice (level 2)
+        let near: Vec<_> = (0..10).map(|i| wheel.insert(i, 1 + i)).collect();
+        for i in 0..5 {
+            wheel.insert(100 + i, 70 + i);
+        }
+        wheel.insert_oneshot(200, 5_000);
+        wheel.insert(201, 5_001);
+        wheel.cancel(near[3]);
+        wheel.cancel(near[3]);
+        assert_eq!(
+            wheel.stats(),
+            WheelStats {
+                total_inserted: 17,
+                total_cancelled: 1,
+                current_live: 16,
+                #[cfg(feature = "alloc-tagging")]
+                heap_bytes: wheel.heap_bytes(),
+                ..WheelStats::default()
+            }
+        );
+
+        wheel.advance_to(6_000, &mut expired).unwrap();
+        let stats = wheel.stats();
+        assert_eq!(stats.total_expired, 16);
+        assert_eq!(stats.total_cascades, 5 + 2 * 2);
+        assert_eq!(stats.current_live, 0);
+
+        // Flushes and bulk removals are counted too
+        for i in 0..3 {
+            wheel.insert(i, 7_000 + i);
+        }
+        wheel.retain(|&task, _| task != 0);
+        wheel.expire_all(&mut expired);
+        let stats = wheel.stats();
+        assert_eq!(
+            (
+                stats.total_inserted,
+                stats.total_expired,
+                stats.total_cancelled
+            ),
+            (20, 18, 2)
+        );
+    }
+
+    #[test]
+    fn test_shrink_to_fit_after_burst() {
+        let mut wheel = TimingWheel::new();
+        let survivor = wheel.insert(u64::MAX, 5_000);
+        for i in 0..20_000u64 {
+            wheel.insert(i, 1 + i % 100);
+        }
+        let mut expired = Vec::new();
+        wheel.advance_to(200, &mut expired).unwrap();
+        assert_eq!(expired.len(), 20_000);
+
+        let grown = wheel.capacity();
+        #[cfg(feature = "alloc-tagging")]
+        let peak = wheel.stats().heap_bytes;
+        wheel.shrink_to_fit();
+        assert!(wheel.capacity() < grown);
+        #[cfg(feature = "alloc-tagging")]
+        assert!(wheel.stats().heap_bytes < peak);
+        assert_eq!(wheel.deadline_of(survivor), Some(5_000));
+
+        wheel.advance_to(5_001, &mut expired).unwrap();
+        assert_eq!(expired.last(), Some(&u64::MAX));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_auto_shrink_follows_peak_and_trough() {
+        use crate::clock::MockClock;
+
+        let observer = RecordingObserver::default();
+        let mut wheel = TimingWheel::builder().auto_shrink(25, 100).build();
+        wheel.set_observer(observer.clone());
+        let clock = MockClock::new(wheel.tick_to_instant(0));
+
+        // A few long-lived timers, then a daily peak of short ones
+        let survivors: Vec<_> = (0..10).map(|i| wheel.insert(i, 1_000_000)).collect();
+        for i in 0..50_000u64 {
+            wheel.insert(i, 1 + i % 2_000);
+        }
+        let peak = wheel.capacity();
+
+        // Most of the peak fires but utilization stays within the hysteresis band
+        let mut expired = Vec::new();
+        wheel.advance_to(1_000, &mut expired).unwrap();
+        assert!(wheel.len() * 4 > peak);
+        assert_eq!(wheel.shrink_count(), 0);
+        assert_eq!(wheel.capacity(), peak);
+
+        // Let the trough arrive on the mock clock
+        while wheel.current_time() <= 2_000 {
+            wheel.wait_next_with(&clock);
+        }
+        wheel.advance_to(2_100, &mut expired).unwrap();
+        assert!(wheel.capacity() < peak);
+        assert!(wheel.shrink_count() >= 1);
+        assert!(observer.events().iter().all(|e| e.starts_with("shrink")));
+        assert!(survivors.iter().all(|&key| wheel.contains(key)));
+
+        // Staying in the trough does not keep shrinking
+        let trough = wheel.capacity();
+        let shrinks = wheel.shrink_count();
+        wheel.advance_to(5_000, &mut expired).unwrap();
+        assert_eq!((wheel.capacity(), wheel.shrink_count()), (trough, shrinks));
+
+        // The next peak grows the slab again without disturbing the survivors
+        for i in 0..50_000u64 {
+            wheel.insert(i, 10_000);
+        }
+        assert!(wheel.capacity() >= 50_000);
+        assert!(survivors.iter().all(|&key| wheel.contains(key)));
+        assert_eq!(wheel.deadline_of(survivors[0]), Some(1_000_000));
+    }
+
+    #[test]
+    fn test_peek_next_deadline() {
+        let mut wheel = TimingWheel::new();
+        assert_eq!(wheel.peek_next_deadline(), None);
+
+        // A single near timer
+        let near = wheel.insert("near", 7);
+        assert_eq!(wheel.peek_next_deadline(), Some(7));
+        wheel.cancel(near);
+
+        // Only level 3 is occupied
+        wheel.insert("level 3", 300_000);
+        assert_eq!(wheel.peek_next_deadline(), Some(300_000));
+
+        // A level-1 entry that has not cascaded yet beats a later level-0 entry
+        let mut expired = Vec::new();
+        wheel.insert("level 1", 100);
+        wheel.advance_to(70, &mut expired).unwrap();
+        wheel.insert("level 0", 130);
+        assert_eq!(wheel.peek_next_deadline(), Some(100));
+
+        wheel.advance_to(101, &mut expired).unwrap();
+        assert_eq!(expired, vec!["level 1"]);
+        assert_eq!(wheel.peek_next_deadline(), Some(130));
+    }
+
+    #[test]
+    fn test_peek_next_deadline_matches_full_scan() {
+        let mut next = lcg(0x853c_49e6_748f_ea9b_u64);
+
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        // Shadow copy of every pending (key, deadline), scanned naively
+        let mut pending: Vec<(Key, u64)> = Vec::new();
+        for step in 0..2_000 {
+            match next(4) {
+                0 | 1 => {
+                    let deadline =
+                        wheel.current_time() + next(if step % 2 == 0 { 100 } else { 400_000 });
+                    pending.push((wheel.insert(step, deadline), deadline));
+                }
+                2 if !pending.is_empty() => {
+                    let (key, _) = pending.swap_remove(next(pending.len() as u64) as usize);
+                    assert!(wheel.cancel(key).is_some());
+                }
+                _ => {
+                    wheel.tick_n(1 + next(300), &mut expired);
+                    pending.retain(|&(key, _)| wheel.contains(key));
+                }
+            }
+            let earliest = pending.iter().map(|&(_, deadline)| deadline).min();
+            assert_eq!(wheel.peek_next_deadline(), earliest, "step {step}");
+        }
+    }
+
+    #[test]
+    fn test_peek_next_deadline_sees_next_rotation_and_overflow() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        wheel.advance_to(70, &mut expired).unwrap();
+
+        // Lands in level 1's current slot, one full rotation ahead
+        wheel.insert("next rotation", 4_160);
+        assert_eq!(wheel.peek_next_deadline(), Some(4_160));
+        wheel.insert("far", HORIZON * 2);
+        assert_eq!(wheel.peek_next_deadline(), Some(4_160));
+
+        wheel.advance_to(4_161, &mut expired).unwrap();
+        assert_eq!(expired, vec!["next rotation"]);
+        assert_eq!(wheel.peek_next_deadline(), Some(HORIZON * 2));
+    }
+
+    #[test]
+    fn test_reschedule_past_horizon_moves_to_overflow() {
+        let mut wheel = TimingWheel::new();
+        let key = wheel.insert("moved", 50);
+        assert_eq!(wheel.overflow_len(), 0);
+
+        let far = HORIZON + 1_000;
+        assert!(wheel.reschedule(key, far).is_some());
+        assert_eq!(wheel.overflow_len(), 1);
+        assert_eq!(
+            wheel.slab.get(key).map(|e| e.level),
+            Some(wheel.config().overflow_level())
+        );
+
+        let mut expired = Vec::new();
+        wheel.advance_to(far, &mut expired).unwrap();
+        assert!(expired.is_empty(), "nothing may fire at the old deadline");
+        assert_eq!(wheel.overflow_len(), 0);
+
+        wheel.tick(&mut expired);
+        assert_eq!(expired, vec!["moved"]);
+
+        // And back: an overflow timer pulled into range leaves the overflow set
+        let key = wheel.insert("returning", wheel.current_time() + HORIZON * 3);
+        assert_eq!(wheel.overflow_len(), 1);
+        assert!(wheel.reschedule(key, wheel.current_time() + 10).is_some());
+        assert_eq!(wheel.overflow_len(), 0);
+        assert_eq!(wheel.peek_next_deadline(), Some(far + 11));
+    }
+
+    #[test]
+    fn test_advance_to_crosses_levels_in_one_call() {
+        let mut wheel = TimingWheel::new();
+        wheel.insert(5_000, 5_000);
+        wheel.insert(3, 3);
+        wheel.insert(70, 70);
+
+        let mut expired = Vec::new();
+        wheel.advance_to(6_000, &mut expired).unwrap();
+        assert_eq!(expired, vec![3, 70, 5_000]);
+        assert_eq!(wheel.current_time(), 6_000);
+    }
+
+    #[test]
+    fn test_advance_to_matches_ticking_one_by_one() {
+        // Small LCG so the schedule is reproducible without extra dependencies
+        let mut next = lcg(0x2545_f491_4f6c_dd1d_u64);
+
+        let mut fast = TimingWheel::new();
+        let mut slow = TimingWheel::new();
+        for i in 0..500u64 {
+            // Mix of level-0, upper-level and overflow deadlines
+            let deadline = match i % 3 {
+                0 => next(64),
+                1 => next(300_000),
+                _ => HORIZON + next(5_000),
+            };
+            fast.insert(i, deadline);
+            slow.insert(i, deadline);
+        }
+
+        let mut fast_expired = Vec::new();
+        let mut slow_expired = Vec::new();
+        let mut now = 0;
+        while now < HORIZON + 6_000 {
+            now += 1 + next(200_000);
+            fast.advance_to(now, &mut fast_expired).unwrap();
+            while slow.current_time() < now {
+                slow.tick(&mut slow_expired);
+            }
+            assert_eq!(fast_expired, slow_expired, "diverged at tick {now}");
+        }
+        assert_eq!(fast_expired.len(), 500);
+    }
+
+    #[test]
+    fn test_advance_to_jumps_around_level_boundaries_match_ticking() {
+        let mut next = lcg(0xd1b5_4a32_d192_ed03_u64);
+
+        // Deadlines and jump targets cluster within a few ticks of each level boundary
+        let boundaries = [64u64, 4_096, 262_144, 3 * 262_144];
+        for case in 0..8u64 {
+            let mut fast = TimingWheel::new();
+            let mut slow = TimingWheel::new();
+            for i in 0..200u64 {
+                let edge = boundaries[next(4) as usize] * (1 + next(3));
+                let deadline = (edge + next(7)).saturating_sub(3);
+                fast.insert(i, deadline);
+                slow.insert(i, deadline);
+            }
+
+            let mut fast_expired = Vec::new();
+            let mut slow_expired = Vec::new();
+            while !fast.is_empty() {
+                let edge = boundaries[next(4) as usize] * (1 + next(4));
+                let target = if edge > fast.current_time() {
+                    (edge + next(5)).saturating_sub(2)
+                } else {
+                    fast.current_time() + 1 + next(100_000)
+                };
+                let target = target.max(fast.current_time());
+                fast.advance_to(target, &mut fast_expired).unwrap();
+                while slow.current_time() < target {
+                    slow.tick(&mut slow_expired);
+                }
+                assert_eq!(
+                    fast_expired, slow_expired,
+                    "case {case} diverged at {target}"
+                );
+            }
+            assert_eq!(fast_expired.len(), 200);
+            assert!(slow.is_empty());
+
+            // Re-targeting the current tick is a no-op
+            let now = fast.current_time();
+            fast.advance_to(now, &mut fast_expired).unwrap();
+            assert_eq!(fast.current_time(), now);
+        }
+    }
+
+    #[test]
+    fn test_skip_to_next_event_matches_naive_loop() {
+        let mut next = lcg(0x6a09_e667_f3bc_c908_u64);
+
+        let mut skipping = TimingWheel::new();
+        let mut naive = TimingWheel::new();
+        for i in 0..300u64 {
+            let deadline = match i % 4 {
+                0 => next(64),
+                1 => 50_000 + next(100),
+                2 => next(1_000_000),
+                _ => HORIZON + next(100_000),
+            };
+            skipping.insert(i, deadline);
+            naive.insert(i, deadline);
+        }
+
+        let mut skipped = Vec::new();
+        let mut ticked = Vec::new();
+        let mut events = 0;
+        while let Some(now) = skipping.skip_to_next_event(&mut skipped) {
+            assert_eq!(now, skipping.current_time());
+            while naive.current_time() < now {
+                naive.tick(&mut ticked);
+            }
+            assert_eq!(skipped, ticked, "diverged at tick {now}");
+            events += 1;
+        }
+        assert_eq!(skipped.len(), 300);
+        assert!(naive.is_empty());
+        // Far fewer steps than the ticks covered
+        assert!(events < 5_000, "{events} events");
+        assert_eq!(skipping.skip_to_next_event(&mut skipped), None);
+    }
+
+    #[test]
+    fn test_tick_with_matches_tick() {
+        let mut with = TimingWheel::new();
+        let mut plain = TimingWheel::new();
+        for i in 0..2_000u64 {
+            let deadline = i * 31 % 5_000;
+            with.insert(i, deadline);
+            plain.insert(i, deadline);
+        }
+        with.insert_with_precision(9_999, 300, InsertPrecision::Approximate);
+        plain.insert_with_precision(9_999, 300, InsertPrecision::Approximate);
+
+        let mut called = Vec::new();
+        let mut expired = Vec::new();
+        while !plain.is_empty() {
+            with.tick_with(|task| called.push(task));
+            plain.tick(&mut expired);
+            assert_eq!(called, expired);
+        }
+        assert!(with.is_empty());
+    }
+
+    #[test]
+    fn test_tick_with_panicking_callback_completes_the_tick() {
+        use std::panic::{AssertUnwindSafe, catch_unwind};
+
+        let mut wheel = TimingWheel::new();
+        for i in 0..4 {
+            wheel.insert(i, 10);
+        }
+        let later = wheel.insert(100, 70);
+        wheel.advance_to(10, &mut Vec::new()).unwrap();
+
+        let mut seen = Vec::new();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            wheel.tick_with(|task| {
+                seen.push(task);
+                if seen.len() == 2 {
+                    panic!("callback failed");
+                }
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(seen.len(), 2);
+
+        // The rest of the slot was dropped and the clock moved on
+        assert_eq!(wheel.current_time(), 11);
+        assert_eq!(wheel.len(), 1);
+        assert_eq!(wheel.deadline_of(later), Some(70));
+
+        // Freed slots are reused cleanly and later timers still fire
+        let keys: Vec<_> = (0..4).map(|i| wheel.insert(200 + i, 20)).collect();
+        assert!(keys.iter().all(|&key| wheel.contains(key)));
+        let mut expired = Vec::new();
+        wheel.advance_to(71, &mut expired).unwrap();
+        expired.sort();
+        assert_eq!(expired, vec![100, 200, 201, 202, 203]);
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn test_tick_entries_reports_insert_handles() {
+        let mut wheel = TimingWheel::new();
+        let near = wheel.insert("near", 3);
+        let cascaded = wheel.insert("cascaded", 100);
+        let approx = wheel.insert_with_precision("approx", 200, InsertPrecision::Approximate);
+
+        let mut expired = Vec::new();
+        while !wheel.is_empty() {
+            wheel.tick_entries(&mut expired);
+        }
+        expired.sort_by_key(|&(_, task)| task);
+        assert_eq!(
+            expired,
+            vec![(approx, "approx"), (cascaded, "cascaded"), (near, "near")]
+        );
+
+        // The slot is reused straight away, but the reported handle does not alias it
+        let reused = wheel.insert("reused", 500);
+        assert!(expired.iter().any(|(key, _)| key.index() == reused.index()));
+        assert!(
+            expired
+                .iter()
+                .all(|&(key, _)| key != reused && !wheel.contains(key))
+        );
+    }
+
+    #[test]
+    fn test_iter_yields_every_pending_timer() {
+        let mut wheel = TimingWheel::new();
+        let mut expected = BTreeSet::new();
+        for (task, deadline) in [
+            ("a", 5),
+            ("b", 5),
+            ("c", 90),
+            ("d", 300_000),
+            ("e", HORIZON * 2),
+        ] {
+            expected.insert((wheel.insert(task, deadline), task, deadline));
+        }
+        let gone = wheel.insert("cancelled", 40);
+        wheel.cancel(gone);
+
+        let pending: BTreeSet<_> = wheel
+            .iter()
+            .map(|(key, &task, deadline)| (key, task, deadline))
+            .collect();
+        assert_eq!(pending, expected);
+
+        wheel.advance_to(6, &mut Vec::new()).unwrap();
+        assert_eq!(wheel.iter().count(), 3);
+    }
+
+    #[test]
+    fn test_peek_soonest_looks_across_levels() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        wheel.advance_to(4_000, &mut expired).unwrap();
+
+        // Level 0 (4_010, 4_063), level 1 (4_096, 4_100), level 2 (40_000), level 3
+        // (300_000), the overflow set, and an overdue timer in the current slot
+        for deadline in [4_063, 4_100, 4_096, 4_010, 300_000, 40_000, HORIZON * 3] {
+            wheel.insert(deadline, deadline);
+        }
+        wheel.insert(3_000, 3_000);
+        assert_consistent_links(&wheel);
+
+        let soonest = |wheel: &TimingWheel<u64>, n| -> Vec<u64> {
+            wheel
+                .peek_soonest(n)
+                .into_iter()
+                .map(|(deadline, &task)| {
+                    assert_eq!(deadline, task);
+                    task
+                })
+                .collect()
+        };
+        assert_eq!(soonest(&wheel, 4), vec![3_000, 4_010, 4_063, 4_096]);
+        assert_eq!(
+            soonest(&wheel, 100),
+            vec![
+                3_000,
+                4_010,
+                4_063,
+                4_096,
+                4_100,
+                40_000,
+                300_000,
+                HORIZON * 3
+            ]
+        );
+        assert!(wheel.peek_soonest(0).is_empty());
+        assert_eq!(wheel.len(), 8);
+    }
+
+    #[test]
+    fn test_iter_mut_edits_tasks_in_place() {
+        let mut wheel = TimingWheel::new();
+        let deadlines = [7, 70, 5_123, 300_007, HORIZON + 5];
+        for deadline in deadlines {
+            wheel.insert(deadline, deadline);
+        }
+
+        for (_, task, deadline) in wheel.iter_mut() {
+            *task += deadline;
+        }
+        assert_eq!(wheel.iter_mut().count(), wheel.len());
+        // Level 2 and 3 timers report their own deadlines, not their slot's span
+        let mut seen: Vec<_> = wheel
+            .iter()
+            .map(|(_, &task, deadline)| (deadline, task))
+            .collect();
+        seen.sort();
+        assert_eq!(seen, deadlines.map(|d| (d, 2 * d)));
+        assert_consistent_links(&wheel);
+
+        let mut expired = Vec::new();
+        wheel.advance_to(HORIZON + 6, &mut expired).unwrap();
+        assert_eq!(expired, deadlines.map(|d| 2 * d));
+    }
+
+    #[test]
+    fn test_tick_deadlines_pairs_each_task_with_its_own_deadline() {
+        let mut wheel = TimingWheel::new();
+        wheel.insert("seven", 7);
+        wheel.insert("three", 3);
+
+        let mut expired = Vec::new();
+        for _ in 0..8 {
+            wheel.tick_deadlines(&mut expired);
+        }
+        assert_eq!(expired, vec![(3, "three"), (7, "seven")]);
+    }
+
+    #[test]
+    fn test_tick_deadlines_reports_scheduled_deadline() {
+        let mut wheel = TimingWheel::new();
+        for i in 0..500u64 {
+            wheel.insert(i, i * 97 % 20_000);
+        }
+        wheel.insert_with_precision(1_000, 300, InsertPrecision::Approximate);
+        // Overdue inserts keep their original deadline
+        wheel.advance_to(10, &mut Vec::new()).unwrap();
+        wheel.insert(2_000, 4);
+
+        let mut expired = Vec::new();
+        while !wheel.is_empty() {
+            let tick = wheel.current_time();
+            wheel.tick_deadlines(&mut expired);
+            for &(deadline, task) in &expired {
+                match task {
+                    // Fired by the cascade at the end of tick 255
+                    1_000 => assert_eq!((tick, deadline), (255, 300)),
+                    2_000 => assert_eq!((tick, deadline), (10, 4)),
+                    _ => {
+                        assert_eq!(deadline, task * 97 % 20_000);
+                        assert!(deadline <= tick && deadline < wheel.current_time());
+                    }
+                }
+            }
+            expired.clear();
+        }
+    }
+
+    #[test]
+    fn test_tick_sorted_orders_one_tick_by_deadline() {
+        let build = || {
+            let mut wheel = TimingWheel::new();
+            for task in ["first", "second", "third"] {
+                wheel.insert(task, 5);
+            }
+            wheel.advance_to(5, &mut Vec::new()).unwrap();
+            // Overdue inserts share the current slot with the timers due now
+            wheel.insert("late by 2", 3);
+            wheel.insert("late by 4", 1);
+            wheel
+        };
+
+        let mut sorted = Vec::new();
+        build().tick_sorted(&mut sorted);
+        let mut plain = Vec::new();
+        build().tick(&mut plain);
+
+        assert_eq!(&sorted[..2], ["late by 4", "late by 2"]);
+        // Ties come out in the same order as from `tick`, on every run
+        let ties: Vec<_> = plain
+            .iter()
+            .filter(|task| !task.starts_with("late"))
+            .collect();
+        assert_eq!(sorted[2..].iter().collect::<Vec<_>>(), ties);
+        let mut again = Vec::new();
+        build().tick_sorted(&mut again);
+        assert_eq!(again, sorted);
+    }
+
+    #[test]
+    fn test_tick_sorted_orders_a_cascade_batch() {
+        let build = || {
+            let mut wheel = TimingWheel::new();
+            // Both approximate timers fire when level 1 slot 1 cascades at tick 64,
+            // together with the exact timer due on tick 63
+            wheel.insert_with_precision("approx 100", 100, InsertPrecision::Approximate);
+            wheel.insert_with_precision("approx 120", 120, InsertPrecision::Approximate);
+            wheel.insert("exact 63", 63);
+            wheel.advance_to(63, &mut Vec::new()).unwrap();
+            wheel
+        };
+
+        let mut plain = Vec::new();
+        build().tick(&mut plain);
+        assert_eq!(plain, vec!["exact 63", "approx 120", "approx 100"]);
+
+        let mut sorted = Vec::new();
+        build().tick_sorted(&mut sorted);
+        assert_eq!(sorted, vec!["exact 63", "approx 100", "approx 120"]);
+    }
+
+    #[test]
+    fn test_tick_budgeted_spreads_a_mass_expiry() {
+        let mut wheel = TimingWheel::new();
+        for i in 0..10_000u64 {
+            wheel.insert(i, 5);
+        }
+        wheel.insert(10_000, 6);
+        wheel.advance_to(5, &mut Vec::new()).unwrap();
+
+        let mut expired = Vec::new();
+        let mut calls = 0;
+        while wheel.tick_budgeted(&mut expired, 100) {
+            calls += 1;
+            assert_eq!(expired.len(), calls * 100);
+            assert_eq!(wheel.current_time(), 5, "advanced with timers still due");
+            if calls == 50 {
+                // A late insert joins the slot being drained instead of waiting
+                wheel.insert(20_000, 0);
+            }
+        }
+        assert_eq!(wheel.current_time(), 6);
+
+        // Nothing lost or duplicated, and still in insertion order
+        let mut expected: Vec<u64> = (0..10_000).collect();
+        expected.push(20_000);
+        assert_eq!(expired, expected);
+
+        // The next tick fits in the budget and completes in one call
+        assert!(!wheel.tick_budgeted(&mut expired, 100));
+        assert_eq!(expired.last(), Some(&10_000));
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn test_tick_n_matches_naive_loop() {
+        let mut next = lcg(0x9e37_79b9_7f4a_7c15_u64);
+
+        let mut batched = TimingWheel::new();
+        let mut naive = TimingWheel::new();
+        let mut batched_expired = Vec::new();
+        let mut naive_expired = Vec::new();
+        let mut keys = Vec::new();
+
+        // Variable sleeps with new timers armed and old ones cancelled in between
+        for round in 0..300u64 {
+            for i in 0..4 {
+                let deadline = batched.current_time() + next(if i == 0 { 64 } else { 50_000 });
+                let key = batched.insert(round * 4 + i, deadline);
+                keys.push((key, naive.insert(round * 4 + i, deadline)));
+            }
+            if round % 3 == 0 {
+                let (victim, naive_victim) = keys.swap_remove(next(keys.len() as u64) as usize);
+                assert_eq!(batched.cancel(victim), naive.cancel(naive_victim));
+            }
+
+            let n = next(5_000);
+            batched.tick_n(n, &mut batched_expired);
+            for _ in 0..n {
+                naive.tick(&mut naive_expired);
+            }
+            assert_eq!(batched.current_time(), naive.current_time());
+            assert_eq!(batched_expired, naive_expired, "diverged in round {round}");
+        }
+
+        batched.tick_n(0, &mut batched_expired);
+        assert_eq!(batched_expired, naive_expired);
+        assert_eq!(batched.len(), naive.len());
+    }
+
+    #[test]
+    fn test_touch_bumps_relative_to_now() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        let idle = wheel.insert("idle", 30);
+
+        // Activity every 20 ticks keeps it alive, alternating between level 0 and level 1
+        for round in 0..10u64 {
+            wheel.advance_to(round * 20 + 20, &mut expired).unwrap();
+            let extra = if round % 2 == 0 { 30 } else { 100 };
+            assert_eq!(wheel.touch(idle, extra), Some(wheel.current_time() + extra));
+        }
+        assert!(expired.is_empty());
+        let last = wheel.deadline_of(idle).unwrap();
+        assert_eq!(last, 200 + 100);
+
+        wheel.advance_to(last, &mut expired).unwrap();
+        assert!(expired.is_empty());
+        wheel.advance_to(last + 500, &mut expired).unwrap();
+        assert_eq!(expired, vec!["idle"]);
+
+        assert_eq!(wheel.touch(idle, 30), None);
+    }
+
+    #[test]
+    fn test_level_boundary_deadlines_fire_exactly_once_on_time() {
+        const BOUNDARIES: [u64; 3] = [64, 4_096, 262_144];
+        let end = 2 * BOUNDARIES[2] + 2;
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        let (mut armed, mut fired) = (0, 0);
+
+        while wheel.current_time() < end {
+            let tick = wheel.current_time();
+            // Just before every level-0 rotation, arm timers either side of the next
+            // multiple of each boundary. Seen from different ticks, the same deadline
+            // starts out on different levels and takes a different cascade path.
+            if tick % 64 == 61 || tick == 0 {
+                for boundary in BOUNDARIES {
+                    let next = (tick / boundary + 1) * boundary;
+                    for deadline in [next - 1, next, next + 1] {
+                        if deadline > tick && deadline < end {
+                            wheel.insert(deadline, deadline);
+                            armed += 1;
+                        }
+                    }
+                }
+            }
+
+            wheel.tick(&mut expired);
+            for deadline in expired.drain(..) {
+                assert_eq!(deadline, tick, "fired on the wrong tick");
+                fired += 1;
+            }
+        }
+        assert_eq!(fired, armed);
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn test_bucket_start_deadline_fires_on_its_own_tick() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        // Exactly on the start of level 1's slot 1 and level 2's slot 1
+        wheel.insert("64", 64);
+        wheel.insert("4096", 4_096);
+
+        wheel.advance_to(64, &mut expired).unwrap();
+        assert!(expired.is_empty(), "a cascade must not expire early");
+        wheel.tick(&mut expired);
+        assert_eq!(expired, vec!["64"]);
+
+        wheel.advance_to(4_096, &mut expired).unwrap();
+        assert_eq!(expired, vec!["64"]);
+        wheel.tick(&mut expired);
+        assert_eq!(expired, vec!["64", "4096"]);
+    }
+
+    #[test]
+    fn test_clear_level_only_drops_that_level() {
+        let mut wheel = TimingWheel::new();
+        let near = wheel.insert("level 0", 10);
+        let mid: Vec<_> = (0..5)
+            .map(|i| wheel.insert("level 1", 100 + i * 300))
+            .collect();
+        let far = wheel.insert("level 2", 10_000);
+
+        wheel.clear_level(1);
+        assert!(mid.iter().all(|&key| !wheel.contains(key)));
+        assert!(wheel.contains(near) && wheel.contains(far));
+
+        let mut expired = Vec::new();
+        wheel.advance_to(10_001, &mut expired).unwrap();
+        assert_eq!(expired, vec!["level 0", "level 2"]);
+        assert_eq!(wheel.current_time(), 10_001);
+    }
+
+    #[test]
+    fn test_with_capacity_avoids_reallocation() {
+        let mut wheel = TimingWheel::with_capacity(50_000);
+        let capacity = wheel.capacity();
+        assert!(capacity >= 50_000);
+        for i in 0..50_000u64 {
+            wheel.insert(i, i % 10_000);
+        }
+        assert_eq!(wheel.capacity(), capacity);
+
+        // Composes with derived defaults
+        #[derive(Default)]
+        struct Server {
+            timeouts: TimingWheel<u32>,
+        }
+        assert_eq!(Server::default().timeouts.current_time(), 0);
+    }
+
+    #[test]
+    fn test_custom_config_fires_on_exact_ticks() {
+        // Three levels of 16 slots: a 4096-tick horizon
+        let mut wheel = TimingWheel::with_config(WheelConfig::new(4, 3));
+        for deadline in [3, 16, 17, 255, 256, 4_000, 5_000, 9_999] {
+            wheel.insert(deadline, deadline);
+        }
+        assert_eq!(wheel.overflow_len(), 2);
+        assert_eq!(wheel.peek_next_deadline(), Some(3));
+
+        let mut expired = Vec::new();
+        while wheel.current_time() <= 10_000 {
+            let tick = wheel.current_time();
+            wheel.tick(&mut expired);
+            assert!(expired.drain(..).all(|deadline| deadline == tick));
+        }
+        assert!(wheel.is_empty());
+
+        // Batch advancing agrees with single steps under the same layout
+        let mut wheel = TimingWheel::builder()
+            .config(WheelConfig::new(3, 2))
+            .build();
+        for deadline in [1, 8, 63, 64, 70, 500] {
+            wheel.insert(deadline, deadline);
+        }
+        wheel.advance_to(501, &mut expired).unwrap();
+        assert_eq!(expired, vec![1, 8, 63, 64, 70, 500]);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit in u64")]
+    fn test_with_config_rejects_oversized_horizon() {
+        let _: TimingWheel<()> = TimingWheel::with_config(WheelConfig::new(8, 8));
+    }
+
+    #[test]
+    fn test_approximate_timer_fires_within_its_slot_span() {
+        let mut wheel = TimingWheel::new();
+        let mut fired_at = Vec::new();
+        let mut expired = Vec::new();
+
+        // Level 1, slot 1 spans ticks 64..128
+        wheel.insert_with_precision("approx", 100, InsertPrecision::Approximate);
+        wheel.insert_with_precision("exact", 100, InsertPrecision::Exact);
+        // Level 0 approximate timers are not affected
+        wheel.insert_with_precision("near", 5, InsertPrecision::Approximate);
+
+        while wheel.current_time() <= 100 {
+            let tick = wheel.current_time();
+            wheel.tick(&mut expired);
+            fired_at.extend(expired.drain(..).map(|task| (task, tick)));
+        }
+
+        assert_eq!(fired_at[0], ("near", 5));
+        let (_, approx) = fired_at.iter().find(|(task, _)| *task == "approx").unwrap();
+        assert!((100 - 64..=100).contains(approx), "fired at {approx}");
+        assert!(*approx < 100, "skipped the cascade");
+        assert_eq!(fired_at.last(), Some(&("exact", 100)));
+    }
+
+    #[test]
+    fn test_reschedule_earlier_and_later_fire_on_the_new_tick() {
+        let mut wheel = TimingWheel::new();
+        let earlier = wheel.insert("earlier", 5_000);
+        let later = wheel.insert("later", 30);
+
+        let earlier = wheel.reschedule(earlier, 200).unwrap();
+        let later = wheel.reschedule(later, 4_100).unwrap();
+
+        let mut fired_at = Vec::new();
+        let mut expired = Vec::new();
+        while wheel.current_time() <= 5_000 {
+            let tick = wheel.current_time();
+            wheel.tick(&mut expired);
+            fired_at.extend(expired.drain(..).map(|task| (task, tick)));
+        }
+        assert_eq!(fired_at, vec![("earlier", 200), ("later", 4_100)]);
+        assert!(!wheel.contains(earlier) && !wheel.contains(later));
+    }
+
+    #[test]
+    fn test_dump_occupancy_shows_where_timers_landed() {
+        let mut wheel = TimingWheel::new();
+        wheel.insert("a", 5);
+        wheel.insert("b", 100);
+        wheel.insert("c", 120);
+        wheel.insert("d", 10_000);
+        wheel.insert("far", HORIZON * 2);
+
+        let occupancy = wheel.dump_occupancy();
+        assert_eq!(occupancy.len(), 4);
+        assert_eq!(occupancy[0][5], 1);
+        assert_eq!(occupancy[1][1], 2);
+        assert_eq!(occupancy[2][2], 1);
+        let linked: usize = occupancy.iter().flatten().sum();
+        assert_eq!(linked, 4);
+
+        let dump = format!("{wheel:?}");
+        assert!(dump.contains("pending: 5"));
+        assert!(
+            dump.contains("levels: [{5: 1}, {1: 2}, {2: 1}, {}]"),
+            "{dump}"
+        );
+
+        // A cascade moves the chain down a level
+        let mut expired = Vec::new();
+        wheel.advance_to(64, &mut expired).unwrap();
+        let occupancy = wheel.dump_occupancy();
+        assert_eq!(occupancy[1][1], 0);
+        assert_eq!((occupancy[0][100 & 63], occupancy[0][120 & 63]), (1, 1));
+    }
+
+    #[test]
+    fn test_bulk_loading() {
+        let mut wheel: TimingWheel<u64> = (0..3_000u64).map(|i| (i, i % 700)).collect();
+        assert_eq!(wheel.len(), 3_000);
+        assert!(wheel.capacity() >= 3_000);
+
+        wheel.extend((0..5_000u64).map(|i| (i, 800)));
+        assert_eq!(wheel.len(), 8_000);
+
+        let keys = wheel.insert_batch([(7, 900), (8, 901)]);
+        assert_eq!(wheel.deadline_of(keys[0]), Some(900));
+        assert_eq!(wheel.cancel(keys[1]), Some(8));
+
+        let mut expired = Vec::new();
+        wheel.advance_to(700, &mut expired).unwrap();
+        assert_eq!(expired.len(), 3_000);
+    }
+
+    #[test]
+    fn test_wheel_from_pairs_ticks_like_one_built_by_hand() {
+        let pairs = [("b", 70), ("a", 3), ("c", 5_000), ("late", 0)];
+        let mut collected: TimingWheel<_> = pairs.into_iter().collect();
+        let mut from_pairs = TimingWheel::from_pairs(pairs);
+        assert_eq!(collected.len(), 4);
+
+        for wheel in [&mut collected, &mut from_pairs] {
+            let mut expired = Vec::new();
+            wheel.tick(&mut expired);
+            assert_eq!(expired, vec!["late"]);
+            wheel.advance_to(5_001, &mut expired).unwrap();
+            assert_eq!(expired, vec!["late", "a", "b", "c"]);
+            assert!(wheel.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_insert_batch_handles_resolve() {
+        let mut wheel = TimingWheel::new();
+        wheel.insert((u64::MAX, "existing"), 5);
+
+        let keys = wheel.insert_batch((0..1_000u64).map(|i| ((i, "batch"), i * 37 % 10_000)));
+        assert_eq!(keys.len(), 1_000);
+        assert_eq!(wheel.len(), 1_001);
+        for (i, &key) in keys.iter().enumerate() {
+            let i = i as u64;
+            assert_eq!(wheel.get(key), Some(&(i, "batch")));
+            assert_eq!(wheel.deadline_of(key), Some(i * 37 % 10_000));
+        }
+
+        // One reservation sized from the hint, where pushing one by one would have
+        // doubled its way up to 2^16 slots
+        let mut wheel = TimingWheel::with_capacity(0);
+        let keys = wheel.insert_batch((0..50_000u64).map(|i| (i, i)));
+        assert!(keys.iter().map(|key| key.index().get()).eq(1..=50_000));
+        assert!((50_000..65_536).contains(&wheel.capacity()));
+    }
+
+    #[test]
+    fn test_deadline_range_follows_current_tick() {
+        let mut wheel = TimingWheel::new();
+        assert_eq!(wheel.min_deadline(), 0);
+        assert_eq!(wheel.max_deadline(), 64u64.pow(4) - 1);
+
+        wheel.advance_to(1_000, &mut Vec::new()).unwrap();
+        assert_eq!(wheel.min_deadline(), 1_000);
+        assert_eq!(wheel.max_deadline(), 1_000 + HORIZON - 1);
+
+        // The edge of the range still lands in the wheels
+        wheel.insert("last in range", wheel.max_deadline());
+        assert_eq!(wheel.overflow_len(), 0);
+        wheel.insert("parked", wheel.max_deadline() + 1);
+        assert_eq!(wheel.overflow_len(), 1);
+
+        let small: TimingWheel<()> = TimingWheel::with_config(WheelConfig::new(4, 2));
+        assert_eq!(small.max_deadline(), 255);
+    }
+
+    #[test]
+    fn test_tick_iter() {
+        let mut wheel = TimingWheel::new();
+        wheel.insert("a", 0);
+        wheel.insert("b", 0);
+        wheel.insert("c", 1);
+        wheel.insert_with_precision("approx", 100, InsertPrecision::Approximate);
+
+        let mut fired = wheel.tick_iter().collect::<Vec<_>>();
+        fired.sort();
+        assert_eq!(fired, vec!["a", "b"]);
+        assert_eq!(wheel.current_time(), 1);
+
+        // Dropped early: the tick still completes
+        let mut partial = wheel.tick_iter();
+        assert_eq!(partial.next(), Some("c"));
+        drop(partial);
+        assert_eq!(wheel.current_time(), 2);
+
+        // Cascade output comes through the iterator too
+        let mut expired = Vec::new();
+        wheel.advance_to(63, &mut expired).unwrap();
+        assert!(expired.is_empty());
+        assert_eq!(wheel.tick_iter().collect::<Vec<_>>(), vec!["approx"]);
+
+        let mut dropped_unread = TimingWheel::new();
+        let key = dropped_unread.insert(String::from("unread"), 0);
+        drop(dropped_unread.tick_iter());
+        assert!(!dropped_unread.contains(key));
+        assert_eq!(dropped_unread.current_time(), 1);
+    }
+
+    #[test]
+    fn test_into_iter_yields_live_timers_with_true_deadlines() {
+        let mut wheel = TimingWheel::new();
+        let mut keys = Vec::new();
+        for deadline in [3, 70, 5_000, 300_000, HORIZON * 2] {
+            keys.push(wheel.insert(deadline, deadline));
+        }
+        wheel.cancel(keys[1]);
+        let extra = wheel.insert(42, 42);
+        wheel.cancel(extra);
+
+        // Cascade one of them so it sits in a different slot than it started in
+        let mut expired = Vec::new();
+        wheel.advance_to(4_096, &mut expired).unwrap();
+        assert_eq!(expired, vec![3]);
+
+        let remaining = wheel.into_iter();
+        assert_eq!(remaining.len(), 3);
+        let mut remaining: Vec<_> = remaining.collect();
+        remaining.sort();
+        assert_eq!(
+            remaining,
+            vec![
+                (5_000, 5_000),
+                (300_000, 300_000),
+                (HORIZON * 2, HORIZON * 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_len_tracks_inserts_cancels_and_expiries() {
+        let mut wheel = TimingWheel::new();
+        assert!(wheel.is_empty());
+
+        // Five near ones, five that cascade from level 1 first
+        let keys: Vec<_> = (0..10u64)
+            .map(|i| wheel.insert(i, if i < 5 { i + 1 } else { 100 + i }))
+            .collect();
+        assert_eq!(wheel.len(), 10);
+        for &key in &keys[7..] {
+            wheel.cancel(key);
+        }
+        assert_eq!(wheel.len(), 7);
+
+        let mut expired = Vec::new();
+        // Crossing 64 cascades 5 and 6 down to level 0 without changing the count
+        wheel.advance_to(65, &mut expired).unwrap();
+        assert_eq!(expired.len(), 5);
+        assert_eq!(wheel.len(), 2);
+        assert!(!wheel.is_empty());
+    }
+
+    #[test]
+    fn test_clear_resets_and_keeps_capacity() {
+        let mut wheel = TimingWheel::new();
+        let old: Vec<_> = (0..5_000u64).map(|i| wheel.insert(i, i * 7)).collect();
+        let mut expired = Vec::new();
+        wheel.advance_to(100, &mut expired).unwrap();
+        let capacity = wheel.capacity();
+
+        wheel.clear();
+        assert!(wheel.is_empty());
+        assert_eq!(wheel.current_time(), 0);
+        assert_eq!(wheel.capacity(), capacity);
+        assert!(old.iter().all(|&key| !wheel.contains(key)));
+
+        let fresh = wheel.insert(1, 3);
+        assert_eq!(fresh.index().get(), 1);
+        assert_ne!(fresh, old[0]);
+        wheel.advance_to(4, &mut expired).unwrap();
+        assert_eq!(expired.last(), Some(&1));
+    }
+
+    #[test]
+    fn test_cancel_group_takes_only_pending_members() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        // Session 7's timers on several levels and in the overflow set, next to session 8
+        let handshake = wheel.insert_grouped("7 handshake", 3, 7);
+        let retransmit = wheel.insert_grouped("7 retransmit", 40, 7);
+        wheel.insert_grouped("7 idle", 5_000, 7);
+        wheel.insert_grouped("7 keepalive", 2 * HORIZON, 7);
+        let other = wheel.insert_grouped("8 idle", 5_000, 8);
+        let plain = wheel.insert("ungrouped", 5_000);
+
+        // One fires, one is cancelled on its own, one moves
+        wheel.advance_to(10, &mut expired).unwrap();
+        assert_eq!(expired, vec!["7 handshake"]);
+        assert!(!wheel.contains(handshake));
+        wheel.cancel(retransmit);
+        let idle = wheel
+            .iter()
+            .find(|&(_, &task, _)| task == "7 idle")
+            .unwrap()
+            .0;
+        wheel.reschedule(idle, 6_000);
+
+        let mut cancelled = wheel.cancel_group(7);
+        cancelled.sort();
+        assert_eq!(cancelled, vec!["7 idle", "7 keepalive"]);
+        assert!(wheel.cancel_group(7).is_empty());
+        assert!(wheel.cancel_group(9).is_empty());
+
+        assert_eq!(wheel.len(), 2);
+        assert!(wheel.contains(other) && wheel.contains(plain));
+        assert_consistent_links(&wheel);
+    }
+
+    #[test]
+    fn test_retain_even_tags_across_levels() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        // Tagged by connection, spread over every level and the overflow set
+        for tag in 0..40u64 {
+            for deadline in [3, 90, 7_000, 300_000, 2 * HORIZON] {
+                wheel.insert((tag, deadline), deadline + tag);
+            }
+        }
+
+        assert_eq!(wheel.retain(|&(tag, _), _| tag % 2 == 0), 100);
+        assert_eq!(wheel.len(), 100);
+        assert_consistent_links(&wheel);
+        assert_eq!(wheel.retain(|_, _| true), 0);
+
+        while let Some(deadline) = wheel.peek_next_deadline() {
+            wheel.advance_to(deadline + 1, &mut expired).unwrap();
+        }
+        assert_eq!(expired.len(), 100);
+        assert!(expired.iter().all(|&(tag, _)| tag % 2 == 0));
+    }
+
+    #[test]
+    fn test_retain_unlinks_head_middle_and_tail() {
+        let mut wheel = TimingWheel::new();
+        // One chain in a single bucket, 0 at its head and 4 at its tail
+        let keys: Vec<_> = (0..5u32).map(|i| wheel.insert(i, 10)).collect();
+        let other = wheel.insert(100, 100);
+        let parked = wheel.insert(200, HORIZON * 2);
+        let dropped_far = wheel.insert(201, HORIZON * 3);
+
+        assert_eq!(wheel.retain(|&task, _| !matches!(task, 4 | 2 | 0 | 201)), 4);
+        assert_eq!(wheel.len(), 4);
+        assert!(!wheel.contains(keys[4]) && !wheel.contains(keys[2]) && !wheel.contains(keys[0]));
+        assert!(!wheel.contains(dropped_far));
+        assert_eq!(wheel.overflow_len(), 1);
+        assert_eq!(wheel.dump_occupancy()[0][10], 2);
+
+        let mut expired = Vec::new();
+        wheel.advance_to(10, &mut expired).unwrap();
+        assert!(expired.is_empty());
+        wheel.tick(&mut expired);
+        expired.sort();
+        assert_eq!(expired, vec![1, 3]);
+
+        // Deadlines are visible to the predicate too
+        assert_eq!(wheel.retain(|_, deadline| deadline < 1_000), 1);
+        assert!(wheel.contains(other) && !wheel.contains(parked));
+        wheel.advance_to(101, &mut expired).unwrap();
+        assert_eq!(expired, vec![1, 3, 100]);
+    }
+
+    #[test]
+    fn test_expire_all_flushes_in_deadline_order() {
+        let mut wheel = TimingWheel::new();
+        for deadline in [HORIZON * 2, 300_000, 3, 5_000, 70, 400_000] {
+            wheel.insert(deadline, deadline);
+        }
+        let mut expired = Vec::new();
+        wheel.advance_to(4, &mut expired).unwrap();
+        assert_eq!(expired, vec![3]);
+
+        expired.clear();
+        wheel.expire_all(&mut expired);
+        assert_eq!(expired, vec![70, 5_000, 300_000, 400_000, HORIZON * 2]);
+        assert!(wheel.is_empty());
+        assert_eq!(wheel.overflow_len(), 0);
+        assert_eq!(wheel.dump_occupancy().iter().flatten().sum::<usize>(), 0);
+        assert_eq!(wheel.current_time(), 4);
+    }
+
+    #[test]
+    fn test_every_payload_is_dropped_exactly_once() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        // Counts drops, and panics on a second drop of the same payload
+        struct Tracked {
+            drops: Rc<Cell<usize>>,
+            dropped: bool,
+        }
+        impl Drop for Tracked {
+            fn drop(&mut self) {
+                assert!(!self.dropped, "double drop");
+                self.dropped = true;
+                self.drops.set(self.drops.get() + 1);
+            }
+        }
+
+        let drops = Rc::new(Cell::new(0));
+        let new = || Tracked {
+            drops: Rc::clone(&drops),
+            dropped: false,
+        };
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        // Cancelling hands the payload back; it drops with the caller's binding
+        let key = wheel.insert(new(), 10);
+        drop(wheel.cancel(key));
+        assert_eq!(drops.get(), 1);
+
+        // Cascading and rescheduling move nothing out of the slab
+        let moved = wheel.insert(new(), 5_000);
+        wheel.insert(new(), 100);
+        wheel.reschedule(moved, 300);
+        wheel.advance_to(200, &mut expired).unwrap();
+        assert_eq!(drops.get(), 1);
+        assert_eq!(expired.len(), 1);
+        expired.clear();
+        assert_eq!(drops.get(), 2);
+
+        // Bulk removals drop what they remove
+        wheel.insert(new(), 250);
+        wheel.insert(new(), 70_000);
+        wheel.retain(|_, deadline| deadline != 250);
+        assert_eq!(drops.get(), 3);
+        wheel.clear_level(2);
+        assert_eq!(drops.get(), 4);
+
+        // A tick iterator dropped early drops the task it did not yield
+        wheel.insert(new(), wheel.current_time());
+        drop(wheel.tick_iter());
+        assert_eq!(drops.get(), 5);
+
+        // Whatever is still pending drops with the wheel: `moved` and a far one
+        wheel.insert(new(), HORIZON * 3);
+        assert_eq!(wheel.len(), 2);
+        drop(wheel);
+        assert_eq!(drops.get(), 7);
+    }
+
+    #[test]
+    fn test_insert_in_is_relative_to_now() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        wheel.advance_to(1_000, &mut expired).unwrap();
+
+        let key = wheel.insert_in("relative", 10);
+        assert_eq!(wheel.deadline_of(key), Some(1_010));
+        let far = wheel.insert_in("saturated", u64::MAX);
+        assert_eq!(wheel.deadline_of(far), Some(u64::MAX));
+
+        wheel.advance_to(1_011, &mut expired).unwrap();
+        assert_eq!(expired, vec!["relative"]);
+    }
+
+    #[test]
+    fn test_same_tick_expirations_come_out_by_priority() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+
+        // Parked in level 1, cascaded into level 0 at tick 64
+        wheel.insert_with_priority("cascaded-5", 100, 5);
+        wheel.insert("cascaded-0", 100);
+        wheel.insert_with_priority("cascaded-9", 100, 9);
+        wheel.advance_to(70, &mut expired).unwrap();
+        assert!(expired.is_empty());
+
+        // Linked straight into the level-0 slot next to the cascaded ones
+        wheel.insert_with_priority("direct-5", 100, 5);
+        wheel.insert_with_priority("direct-9", 100, 9);
+        wheel.insert("direct-0", 100);
+        wheel.insert_with_priority("direct-7", 100, 7);
+        assert_consistent_links(&wheel);
+
+        wheel.advance_to(101, &mut expired).unwrap();
+        assert_eq!(
+            expired,
+            vec![
+                "cascaded-9",
+                "direct-9",
+                "direct-7",
+                "cascaded-5",
+                "direct-5",
+                "cascaded-0",
+                "direct-0",
+            ]
+        );
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn test_clock_saturates_at_the_last_tick() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        wheel.advance_to(u64::MAX - 200, &mut expired).unwrap();
+
+        wheel.insert("short", u64::MAX - 195);
+        // Parked in level 1, cascaded into level 0 at `u64::MAX - 63`
+        wheel.insert("cascaded", u64::MAX - 20);
+        wheel.insert("last", u64::MAX);
+        wheel.insert_in("never", u64::MAX);
+
+        wheel.advance_to(u64::MAX - 194, &mut expired).unwrap();
+        assert_eq!(expired, vec!["short"]);
+        wheel.advance_to(u64::MAX, &mut expired).unwrap();
+        assert_eq!(expired, vec!["short", "cascaded"]);
+
+        // The last tick fires what is due on it, and the clock stays put
+        expired.clear();
+        wheel.tick(&mut expired);
+        assert_eq!(expired, vec!["last", "never"]);
+        assert_eq!(wheel.current_time(), u64::MAX);
+        assert!(wheel.is_empty());
+
+        // Late inserts still fire on the next tick
+        wheel.insert("late", 7);
+        wheel.tick(&mut expired);
+        assert_eq!(expired, vec!["last", "never", "late"]);
+        assert_consistent_links(&wheel);
+
+        // The iterator and budgeted ticks also finish the last tick and stay there
+        wheel.insert_batch([("i1", u64::MAX), ("i2", 3)]);
+        assert_eq!(wheel.tick_iter().collect::<Vec<_>>(), vec!["i1", "i2"]);
+        assert_eq!(wheel.current_time(), u64::MAX);
+        wheel.insert_batch([("b1", u64::MAX), ("b2", u64::MAX), ("b3", 9)]);
+        expired.clear();
+        assert!(wheel.tick_budgeted(&mut expired, 2));
+        assert!(!wheel.tick_budgeted(&mut expired, 2));
+        assert_eq!(expired, vec!["b1", "b2", "b3"]);
+        assert!(!wheel.tick_budgeted(&mut expired, 2));
+        assert_eq!(wheel.current_time(), u64::MAX);
+        assert!(wheel.is_empty());
+
+        // A periodic timer has no period left after the last tick, whichever variant
+        // drives it
+        type Drive = fn(&mut TimingWheel<&'static str>) -> Vec<&'static str>;
+        let variants: [(&str, Drive); 5] = [
+            ("tick", |wheel| {
+                let mut expired = Vec::new();
+                wheel.tick(&mut expired);
+                expired
+            }),
+            ("tick_iter", |wheel| wheel.tick_iter().take(1000).collect()),
+            ("tick_iter dropped", |wheel| {
+                drop(wheel.tick_iter());
+                Vec::new()
+            }),
+            ("tick_with", |wheel| {
+                let mut expired = Vec::new();
+                wheel.tick_with(|task| expired.push(task));
+                expired
+            }),
+            ("tick_budgeted", |wheel| {
+                let mut expired = Vec::new();
+                let mut calls = 0;
+                while wheel.tick_budgeted(&mut expired, 1) {
+                    calls += 1;
+                    assert!(calls < 10, "tick_budgeted never finished");
+                }
+                expired
+            }),
+        ];
+
+        for (name, drive) in variants {
+            let mut wheel = TimingWheel::new();
+            let mut expired = Vec::new();
+            wheel.advance_to(u64::MAX - 5, &mut expired).unwrap();
+            let beat = wheel.insert_periodic("beat", u64::MAX - 2, 1);
+            wheel.advance_to(u64::MAX, &mut expired).unwrap();
+            assert_eq!(expired, vec!["beat", "beat"], "{name}");
+
+            // The last tick fires it once more, or drops it unfired, and frees it
+            let fired = drive(&mut wheel);
+            assert!(fired.len() <= 1, "{name}: {fired:?}");
+            assert!(!wheel.contains(beat), "{name}");
+            assert!(wheel.is_empty(), "{name}");
+            assert!(drive(&mut wheel).is_empty(), "{name}");
+            assert_eq!(wheel.current_time(), u64::MAX, "{name}");
+            assert_consistent_links(&wheel);
+        }
+    }
+
+    #[test]
+    fn test_auto_shrink_checks_stop_at_the_last_tick() {
+        // A huge interval, so getting near the end only stops for one check on the way
+        let mut wheel = TimingWheel::builder().auto_shrink(25, 1 << 63).build();
+        let mut expired = Vec::new();
+        wheel.advance_to(u64::MAX - 10, &mut expired).unwrap();
+
+        // The next check would be past `u64::MAX`, so the skip runs to the deadline
+        wheel.insert("last", u64::MAX);
+        wheel.advance_to(u64::MAX, &mut expired).unwrap();
+        assert!(expired.is_empty());
+        wheel.tick(&mut expired);
+        assert_eq!(expired, vec!["last"]);
+        assert_eq!(wheel.current_time(), u64::MAX);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_wait_next_fires_the_last_tick() {
+        use crate::clock::MockClock;
+
+        let mut wheel = TimingWheel::new_with_resolution(Duration::from_nanos(1));
+        let mut expired = Vec::new();
+        wheel.advance_to(u64::MAX - 10, &mut expired).unwrap();
+        let clock = MockClock::new(wheel.tick_to_instant(u64::MAX - 10));
+
+        wheel.insert("last", u64::MAX);
+        assert_eq!(wheel.wait_next_with(&clock), (u64::MAX, vec!["last"]));
+        assert_eq!(clock.now(), wheel.tick_to_instant(u64::MAX));
+        assert_eq!(wheel.current_time(), u64::MAX);
+    }
+
+    #[test]
+    fn test_level_counts_follow_cascades() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        for deadline in [10, 20, 100, 130, 5_000, HORIZON * 2] {
+            wheel.insert(deadline, deadline);
+        }
+        let cancelled = wheel.insert(0, 140);
+        assert_eq!(wheel.level_counts(), [2, 3, 1, 0]);
+        assert_eq!(wheel.overflow_len(), 1);
+
+        wheel.cancel(cancelled);
+        wheel.advance_to(63, &mut expired).unwrap();
+        assert_eq!(wheel.level_counts(), [0, 2, 1, 0]);
+        // Reaching tick 64 enters level 1 slot 1, moving 100 down; 130 waits for slot 2
+        wheel.tick(&mut expired);
+        assert_eq!(wheel.level_counts(), [1, 1, 1, 0]);
+        assert_eq!(wheel.len(), 4);
+        assert_consistent_links(&wheel);
+
+        wheel.advance_to(4_200, &mut expired).unwrap();
+        assert_eq!(wheel.level_counts(), [0, 1, 0, 0]);
+        wheel.clear_level(1);
+        assert_eq!(wheel.level_counts(), [0, 0, 0, 0]);
+        assert_consistent_links(&wheel);
+    }
+
+    #[test]
+    fn test_tick_n_with_reports_each_tick_batch() {
+        let mut wheel = TimingWheel::new();
+        for deadline in [3, 3, 5, 70, 100] {
+            wheel.insert(deadline, deadline);
+        }
+
+        let mut calls = Vec::new();
+        wheel.tick_n_with(90, |tick, expired| {
+            assert!(expired.iter().all(|&deadline| deadline == tick));
+            calls.push((tick, expired.len()));
+            // Leftovers don't bleed into the next tick's batch
+            expired.push(0);
+        });
+        assert_eq!(calls.len(), 90);
+        assert!(calls.iter().map(|&(tick, _)| tick).eq(0..90));
+        let busy: Vec<_> = calls.into_iter().filter(|&(_, n)| n > 0).collect();
+        assert_eq!(busy, vec![(3, 2), (5, 1), (70, 1)]);
+        assert_eq!(wheel.current_time(), 90);
+        assert_eq!(wheel.len(), 1);
+    }
+
+    #[test]
+    fn test_tick_empty_fast_only_takes_idle_ticks() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        wheel.insert("soon", 2);
+        wheel.insert("cascaded", 100);
+
+        let mut slow = Vec::new();
+        while wheel.current_time() <= 100 {
+            let tick = wheel.current_time();
+            if !wheel.tick_empty_fast() {
+                assert_eq!(wheel.current_time(), tick, "a busy tick must not move time");
+                wheel.tick(&mut expired);
+                slow.push(tick);
+            }
+        }
+        // The level-0 expiries, and tick 63 whose step into 64 cascades level 1 slot 1
+        assert_eq!(slow, vec![2, 63, 100]);
+        assert_eq!(expired, vec!["soon", "cascaded"]);
+        assert!(wheel.tick_empty_fast());
+        assert_consistent_links(&wheel);
+    }
+
+    #[test]
+    fn test_cancel_batch_skips_stale_and_repeated_keys() {
+        let mut wheel = TimingWheel::new();
+        let mut other = TimingWheel::new();
+        let mut expired = Vec::new();
+        let keys: Vec<_> = (0..6u64).map(|i| wheel.insert(i, 10 + i * 1_000)).collect();
+        let fired = wheel.insert(99, 1);
+        wheel.advance_to(2, &mut expired).unwrap();
+        let foreign = other.insert(98, 5);
+
+        let mut out = vec![100];
+        let batch = [keys[4], fired, keys[1], keys[4], foreign, keys[0], keys[1]];
+        assert_eq!(wheel.cancel_batch(&batch, &mut out), 3);
+        // Appended after what was already there, in key order
+        assert_eq!(out, vec![100, 4, 1, 0]);
+        assert_eq!(wheel.stats().total_cancelled, 3);
+        assert_eq!(wheel.len(), 3);
+        assert_eq!(other.len(), 1);
+        assert_eq!(wheel.cancel_batch(&keys, &mut out), 3);
+        assert!(wheel.is_empty());
+        assert_consistent_links(&wheel);
+    }
+
+    #[test]
+    fn test_internal_buffer_keeps_its_allocation() {
+        let mut wheel = TimingWheel::new();
+        for i in 0..64u64 {
+            wheel.insert(i, 1);
+        }
+        wheel.tick_internal();
+        wheel.tick_internal();
+        assert_eq!(
+            wheel.take_expired().collect::<Vec<_>>(),
+            (0..64).collect::<Vec<_>>()
+        );
+        let (ptr, capacity) = (wheel.expired.as_ptr(), wheel.expired.capacity());
+
+        for round in 0..500u64 {
+            let now = wheel.current_time();
+            for i in 0..(round % 64) {
+                wheel.insert(i, now);
+            }
+            wheel.tick_internal();
+            assert_eq!(wheel.take_expired().len(), (round % 64) as usize);
+            assert_eq!(wheel.expired.as_ptr(), ptr);
+            assert_eq!(wheel.expired.capacity(), capacity);
+        }
+
+        // Undrained tasks wait for the next take, across ticks
+        wheel.insert(7, wheel.current_time() + 1);
+        wheel.insert(8, wheel.current_time() + 2);
+        for _ in 0..3 {
+            wheel.tick_internal();
+        }
+        assert_eq!(wheel.take_expired().collect::<Vec<_>>(), vec![7, 8]);
+    }
+
+    #[test]
+    fn test_periodic_timer_rearms_until_cancelled() {
+        let mut wheel = TimingWheel::new();
+        let key = wheel.insert_periodic("every 10", 10, 10);
+        wheel.insert("once", 20);
+
+        let mut fired = Vec::new();
+        wheel.tick_n_with(35, |tick, expired| {
+            fired.extend(expired.drain(..).map(|task| (tick, task)));
+        });
+        // Re-arming at tick 10 queues it behind "once", inserted earlier for tick 20
+        assert_eq!(
+            fired,
+            vec![
+                (10, "every 10"),
+                (20, "once"),
+                (20, "every 10"),
+                (30, "every 10")
+            ]
+        );
+        assert!(wheel.contains(key));
+        assert_eq!(wheel.deadline_of(key), Some(40));
+        assert_eq!(wheel.stats().total_expired, 4);
+
+        // Long periods cascade like any timer, and the budgeted tick re-arms too
+        let key = wheel.reschedule(key, 5_000).unwrap();
+        let mut expired = Vec::new();
+        wheel.advance_to(5_000, &mut expired).unwrap();
+        assert!(expired.is_empty());
+        assert!(!wheel.tick_budgeted(&mut expired, 1));
+        assert_eq!(expired, vec!["every 10"]);
+        assert_eq!(wheel.deadline_of(key), Some(5_010));
+        assert_consistent_links(&wheel);
+
+        assert_eq!(wheel.cancel(key), Some("every 10"));
+        wheel.advance_to(6_000, &mut expired).unwrap();
+        assert_eq!(expired.len(), 1);
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn test_periodic_timer_skips_missed_periods() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        wheel.advance_to(100, &mut expired).unwrap();
+
+        // Due 95 ticks ago: fires once now, then keeps its phase from the next tick
+        let key = wheel.insert_periodic(1, 5, 3);
+        wheel.tick(&mut expired);
+        assert_eq!(expired, vec![1]);
+        assert_eq!(wheel.deadline_of(key), Some(101));
+        wheel.advance_to(105, &mut expired).unwrap();
+        assert_eq!(expired, vec![1, 1, 1]);
+        assert_eq!(wheel.deadline_of(key), Some(107));
+    }
+
+    #[test]
+    fn test_paused_wheel_fires_nothing_until_resumed() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        wheel.insert("before", 5);
+        wheel.advance_to(3, &mut expired).unwrap();
+
+        wheel.pause();
+        assert!(wheel.is_paused());
+        let inserted = wheel.insert_in("during", 2);
+        assert_eq!(wheel.deadline_of(inserted), Some(5));
+        wheel.tick(&mut expired);
+        assert!(wheel.advance_to(1_000, &mut expired).is_ok());
+        assert!(wheel.advance_to(1, &mut expired).is_err());
+        wheel.tick_n(50, &mut expired);
+        assert_eq!(wheel.tick_iter().count(), 0);
+        assert!(!wheel.tick_budgeted(&mut expired, 10));
+        assert!(wheel.tick_empty_fast());
+        assert_eq!(wheel.skip_to_next_event(&mut expired), None);
+        assert!(expired.is_empty());
+        assert_eq!(wheel.current_time(), 3);
+        assert_eq!(wheel.len(), 2);
+
+        // Picks up where it stopped: the pause pushed both timers back
+        wheel.resume();
+        wheel.advance_to(5, &mut expired).unwrap();
+        assert!(expired.is_empty());
+        wheel.tick(&mut expired);
+        assert_eq!(expired, vec!["before", "during"]);
+        assert_consistent_links(&wheel);
+    }
+
+    #[test]
+    fn test_resume_and_catch_up_counts_paused_time() {
+        let mut wheel = TimingWheel::new();
+        let mut expired = Vec::new();
+        for deadline in [10, 500, 5_000] {
+            wheel.insert(deadline, deadline);
+        }
+
+        wheel.pause();
+        wheel.tick_n(1_000, &mut expired);
+        wheel.resume_and_catch_up(1_000, &mut expired).unwrap();
+        assert!(!wheel.is_paused());
+        assert_eq!(expired, vec![10, 500]);
+        assert_eq!(wheel.current_time(), 1_000);
+    }
+
+    #[test]
+    fn test_memory_bytes_grows_with_the_slab() {
+        let mut wheel = TimingWheel::new();
+        let empty = wheel.memory_bytes();
+        // The bucket arrays alone: 2 x 256 links and the bitmap
+        assert!(empty > 2 * 256 * size_of::<Option<NonZeroU32>>());
+
+        for i in 0..1_024u64 {
+            wheel.insert(i, i);
+        }
+        assert_eq!(
+            wheel.memory_bytes(),
+            empty,
+            "still within the preallocated slab"
+        );
+        wheel.insert(1_024, 1_024);
+        let grown = wheel.memory_bytes();
+        assert!(grown > empty);
+        #[cfg(feature = "alloc-tagging")]
+        assert!(wheel.heap_bytes() < grown);
+
+        wheel.insert(0, HORIZON * 2);
+        assert_eq!(wheel.memory_bytes(), grown + size_of::<(u64, NonZeroU32)>());
+    }
+
+    #[test]
+    fn test_rebase_keeps_fire_order_and_timing() {
+        const START: u64 = 3_001;
+
+        // The same script on two wheels; returns the live handles
+        let build = || {
+            let mut wheel = TimingWheel::new();
+            let mut keys = Vec::new();
+            for i in 0..600u32 {
+                let deadline = u64::from(i * 7_919 % 9_000);
+                keys.push(wheel.insert_with_priority(i, deadline, (i % 3) as u8));
+            }
+            // Equal deadlines, still parked in level 1 at START while the later inserts
+            // go straight to level 0
+            for i in 600..610 {
+                keys.push(wheel.insert(i, START + 10));
+            }
+            for i in 610..615 {
+                keys.push(wheel.insert(i, HORIZON + u64::from(i)));
+            }
+            let mut expired = Vec::new();
+            wheel.advance_to(START, &mut expired).unwrap();
+            for i in 615..625 {
+                keys.push(wheel.insert(i, START + 10));
+            }
+            // Overdue inserts wait in the current slot
+            keys.push(wheel.insert(625, 17));
+            keys.push(wheel.insert(626, START));
+            keys.retain(|&key| wheel.contains(key));
+            (wheel, keys)
+        };
+        let (mut plain, _) = build();
+        let (mut rebased, keys) = build();
+        let deadlines: Vec<_> = keys.iter().map(|&key| rebased.deadline_of(key)).collect();
+
+        let observer = RecordingObserver::default();
+        rebased.set_observer(observer.clone());
+        rebased.rebase();
+        assert_eq!(rebased.current_time(), 0);
+        assert_eq!(observer.events(), vec![format!("bulk {}", keys.len())]);
+        assert_consistent_links(&rebased);
+        for (&key, deadline) in keys.iter().zip(deadlines) {
+            let shifted = deadline.map(|d| d.saturating_sub(START));
+            assert_eq!(rebased.deadline_of(key), shifted);
+        }
+
+        let fire = |wheel: &mut TimingWheel<u32>| {
+            let base = wheel.current_time();
+            let mut fired = Vec::new();
+            wheel.tick_n_with(7_000, |tick, expired| {
+                fired.extend(expired.drain(..).map(|task| (tick - base, task)));
+            });
+            fired
+        };
+        let expected = fire(&mut plain);
+        assert!(expected.len() > 400);
+        assert_eq!(fire(&mut rebased), expected);
+
+        // The overflow timers, promoted and fired on the renumbered clock
+        let (mut a, mut b) = (Vec::new(), Vec::new());
+        plain.tick_n(HORIZON, &mut a);
+        rebased.tick_n(HORIZON, &mut b);
+        assert_eq!(a, (610..615).collect::<Vec<_>>());
+        assert_eq!(a, b);
+        assert!(rebased.is_empty());
+    }
 }
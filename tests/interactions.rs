@@ -0,0 +1,269 @@
+//! Scripted scenarios that combine several wheel features.
+//!
+//! Each feature is covered by unit tests next to its implementation; these tests pin
+//! down the semantics when features are used together.
+
+use sharded_timing_wheel::TimingWheel;
+#[cfg(feature = "std")]
+use sharded_timing_wheel::{KeyedTimingWheel, ShardedWheel};
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Advances one tick at a time up to `target`, recording the tick each task fired on.
+/// Also checks that no timer fires before its deadline.
+fn run_until<T>(wheel: &mut TimingWheel<T>, target: u64) -> Vec<(u64, T)> {
+    let mut fired = Vec::new();
+    let mut expired = Vec::new();
+    while wheel.current_time() < target {
+        let tick = wheel.current_time();
+        wheel.tick_deadlines(&mut expired);
+        fired.extend(expired.drain(..).map(|(deadline, task)| {
+            assert!(deadline <= tick, "due at {deadline}, fired at {tick}");
+            (tick, task)
+        }));
+    }
+    fired
+}
+
+#[test]
+fn cancel_after_cascade_uses_original_handle() {
+    let mut wheel = TimingWheel::new();
+
+    // Parked in level 1, then cascaded down to level 0 at tick 64
+    let far = wheel.insert("far", 100);
+    let keep = wheel.insert("keep", 101);
+    assert!(run_until(&mut wheel, 80).is_empty());
+
+    // The handle survives the cascade and still cancels the right timer
+    assert_eq!(wheel.cancel(far), Some("far"));
+    assert_eq!(run_until(&mut wheel, 200), vec![(101, "keep")]);
+    assert_eq!(wheel.cancel(keep), None);
+}
+
+#[test]
+fn drained_timers_migrate_to_a_new_wheel_unchanged() {
+    let mut old = TimingWheel::new();
+    for deadline in [10, 70, 5_000, 300_000] {
+        old.insert(deadline, deadline);
+    }
+    assert_eq!(run_until(&mut old, 50), vec![(10, 10)]);
+
+    // Hand the remaining timers over mid-run, keeping absolute deadlines
+    let mut new = TimingWheel::new();
+    assert!(new.advance_to(old.current_time(), &mut Vec::new()).is_ok());
+    for (deadline, task) in old.drain() {
+        new.insert(task, deadline);
+    }
+    assert!(run_until(&mut old, 400_000).is_empty());
+
+    let fired = run_until(&mut new, 400_000);
+    assert_eq!(fired, vec![(70, 70), (5_000, 5_000), (300_000, 300_000)]);
+}
+
+#[test]
+fn clear_after_rejected_backwards_step_restarts_time() {
+    let mut wheel = TimingWheel::new_with_resolution(Duration::from_millis(1));
+    wheel.insert_after("a", Duration::from_millis(20));
+    assert!(run_until(&mut wheel, 10).is_empty());
+
+    // A 30s step back is rejected, then the session is torn down
+    assert!(wheel.advance_to(0, &mut Vec::new()).is_err());
+    wheel.clear();
+    assert_eq!(wheel.current_time(), 0);
+
+    // Relative scheduling after the clear starts from the reset clock
+    wheel.insert_after("b", Duration::from_millis(5));
+    assert_eq!(run_until(&mut wheel, 100), vec![(5, "b")]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn keyed_earliest_across_levels() {
+    let mut wheel = KeyedTimingWheel::new();
+    let mut expired = Vec::new();
+
+    // Armed in level 2, pulled forward into level 1 after some ticks
+    wheel.schedule_earliest("flush", (), 5_000);
+    wheel.advance_to(1_000, &mut expired).unwrap();
+    wheel.schedule_earliest("flush", (), 1_200);
+    // A later request after the move does not push it back out
+    wheel.schedule_earliest("flush", (), 4_000);
+
+    wheel.advance_to(1_200, &mut expired).unwrap();
+    assert!(expired.is_empty());
+    wheel.advance_to(1_201, &mut expired).unwrap();
+    assert_eq!(expired, vec![("flush", ())]);
+
+    wheel.advance_to(6_000, &mut expired).unwrap();
+    assert_eq!(expired.len(), 1);
+}
+
+#[test]
+fn periodic_rescheduled_mid_interval_continues_from_new_deadline() {
+    let mut wheel = TimingWheel::new();
+    let key = wheel.insert_periodic("beat", 10, 10);
+    assert_eq!(run_until(&mut wheel, 15), vec![(10, "beat")]);
+
+    // Pulled in from 20 to 18; the period then counts from 18
+    let key = wheel.reschedule(key, 18).unwrap();
+    assert_eq!(
+        run_until(&mut wheel, 40),
+        vec![(18, "beat"), (28, "beat"), (38, "beat")]
+    );
+    assert_eq!(wheel.deadline_of(key), Some(48));
+
+    // Pushed out into level 1 mid-interval, then cascaded back down before firing
+    let key = wheel.reschedule(key, 200).unwrap();
+    assert_eq!(
+        run_until(&mut wheel, 215),
+        vec![(200, "beat"), (210, "beat")]
+    );
+    assert!(wheel.contains(key));
+}
+
+#[test]
+fn periodic_cancelled_after_first_rearm_stops_recurring() {
+    let mut wheel = TimingWheel::new();
+    let key = wheel.insert_periodic("beat", 100, 70);
+    wheel.insert("other", 300);
+
+    assert_eq!(run_until(&mut wheel, 101), vec![(100, "beat")]);
+    // Re-armed for 170 in level 1 under the same handle, which still cancels it
+    assert_eq!(wheel.deadline_of(key), Some(170));
+    assert_eq!(wheel.cancel(key), Some("beat"));
+    assert_eq!(wheel.cancel(key), None);
+    assert_eq!(run_until(&mut wheel, 1_000), vec![(300, "other")]);
+    assert!(wheel.is_empty());
+}
+
+#[test]
+fn periodic_two_phase_expiry_rearms_in_the_first_phase() {
+    let mut wheel = TimingWheel::new();
+    let key = wheel.insert_periodic("beat", 5, 5);
+
+    // `tick_internal` fires and re-arms at once; only the clones wait in the buffer
+    for _ in 0..=10 {
+        wheel.tick_internal();
+    }
+    assert_eq!(wheel.deadline_of(key), Some(15));
+
+    // Rescheduling between the phases moves the next firing, not what is buffered
+    let key = wheel.reschedule(key, 12).unwrap();
+    assert_eq!(
+        wheel.take_expired().collect::<Vec<_>>(),
+        vec!["beat", "beat"]
+    );
+    assert_eq!(wheel.take_expired().count(), 0);
+    assert_eq!(run_until(&mut wheel, 18), vec![(12, "beat"), (17, "beat")]);
+    assert!(wheel.contains(key));
+}
+
+#[test]
+fn group_cancel_reaches_members_mid_cascade() {
+    let mut wheel = TimingWheel::new();
+    // By tick 4_165, 4_170 has cascaded from level 2 through level 1 into level 0,
+    // 4_500 and 5_000 wait in level 1 and 70_000 is still in level 2
+    for deadline in [4_170, 4_500, 5_000, 70_000] {
+        wheel.insert_grouped(deadline, deadline, 7);
+    }
+    wheel.insert_grouped(1, 4_170, 8);
+    wheel.insert(2, 4_171);
+    assert!(run_until(&mut wheel, 4_165).is_empty());
+
+    let mut cancelled = wheel.cancel_group(7);
+    cancelled.sort();
+    assert_eq!(cancelled, vec![4_170, 4_500, 5_000, 70_000]);
+    assert!(wheel.cancel_group(7).is_empty());
+    assert_eq!(run_until(&mut wheel, 100_000), vec![(4_170, 1), (4_171, 2)]);
+}
+
+#[test]
+fn clear_drops_pending_periodic_timers() {
+    let mut wheel = TimingWheel::new();
+    let key = wheel.insert_periodic("old", 5, 5);
+    wheel.insert_periodic("far", 10_000, 10_000);
+    assert_eq!(run_until(&mut wheel, 11), vec![(5, "old"), (10, "old")]);
+
+    // There are no pinned entries: `clear` drops periodic timers like any other, and
+    // their handles go stale
+    wheel.clear();
+    assert!(wheel.is_empty());
+    assert!(!wheel.contains(key));
+    assert!(run_until(&mut wheel, 30_000).is_empty());
+
+    // Periodic timers after the clear start from the reset clock
+    wheel.clear();
+    wheel.insert_periodic("new", 3, 4);
+    assert_eq!(
+        run_until(&mut wheel, 12),
+        vec![(3, "new"), (7, "new"), (11, "new")]
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn sharded_cancel_after_cascade_routes_to_owning_shard() {
+    let mut wheel = ShardedWheel::new(4);
+    let mut expired = Vec::new();
+
+    // Parked in level 1 of shard 2, cascaded into level 0 at tick 64
+    let key = wheel.insert_on(2, "far", 100);
+    let kept = wheel.insert_on(2, "kept", 101);
+    wheel.insert_on(1, "other shard", 100);
+    wheel.advance_to(80, &mut expired).unwrap();
+    assert!(expired.is_empty());
+    assert_eq!(ShardedWheel::<&str>::shard_of(key), 2);
+
+    assert_eq!(wheel.cancel(key), Some("far"));
+    assert_eq!(wheel.cancel(key), None);
+    wheel.advance_to(200, &mut expired).unwrap();
+    assert_eq!(expired, vec!["other shard", "kept"]);
+    assert_eq!(wheel.cancel(kept), None);
+}
+
+/// `run_until` for a sharded wheel shared between threads, locking it for every tick
+#[cfg(feature = "std")]
+fn run_shared_until<T>(wheel: &Mutex<ShardedWheel<T>>, target: u64) -> Vec<(u64, T)> {
+    let mut fired = Vec::new();
+    let mut expired = Vec::new();
+    loop {
+        let mut wheel = wheel.lock().unwrap();
+        let tick = wheel.shard(0).current_time();
+        if tick >= target {
+            return fired;
+        }
+        wheel.tick(&mut expired);
+        fired.extend(expired.drain(..).map(|task| (tick, task)));
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn sharded_periodic_touched_from_another_thread_then_cancelled() {
+    let wheel = Arc::new(Mutex::new(ShardedWheel::new(4)));
+    let key = wheel.lock().unwrap().insert_periodic_on(3, "beat", 10, 10);
+    wheel.lock().unwrap().insert_on(1, "other", 100);
+    assert_eq!(run_shared_until(&wheel, 15), vec![(10, "beat")]);
+
+    // A keep-alive from another thread pushes the next firing to 15 + 25; the period
+    // is kept and counts from there
+    let remote = Arc::clone(&wheel);
+    let touched = std::thread::spawn(move || remote.lock().unwrap().touch(key, 25));
+    assert_eq!(touched.join().unwrap(), Some(40));
+    assert_eq!(
+        run_shared_until(&wheel, 61),
+        vec![(40, "beat"), (50, "beat"), (60, "beat")]
+    );
+
+    // There is no lazy cancel flag: `cancel` unlinks the timer under the lock, so a tick
+    // on the driving thread either fired it before or never sees it again
+    let remote = Arc::clone(&wheel);
+    let cancelled = std::thread::spawn(move || remote.lock().unwrap().cancel(key));
+    assert_eq!(cancelled.join().unwrap(), Some("beat"));
+    assert_eq!(run_shared_until(&wheel, 300), vec![(100, "other")]);
+
+    let wheel = wheel.lock().unwrap();
+    assert!(wheel.shard(3).is_empty());
+    assert_eq!(wheel.shard(3).current_time(), 300);
+}
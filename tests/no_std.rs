@@ -0,0 +1,29 @@
+//! Drives the core wheel from a `#![no_std]` crate, using nothing beyond `core` and
+//! `alloc`. Run with `--no-default-features` to check the library side as well.
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use sharded_timing_wheel::TimingWheel;
+
+#[test]
+fn insert_tick_cancel_without_std() {
+    let mut wheel = TimingWheel::new();
+    let mut expired = Vec::new();
+
+    let near = wheel.insert(1u32, 3);
+    let cancelled = wheel.insert(2, 70);
+    let far = wheel.insert(3, 5_000);
+    assert_eq!(wheel.cancel(cancelled), Some(2));
+
+    wheel.advance_to(4, &mut expired).unwrap();
+    assert_eq!(expired, vec![1]);
+    assert!(!wheel.contains(near));
+
+    wheel.advance_to(5_001, &mut expired).unwrap();
+    assert_eq!(expired, vec![1, 3]);
+    assert!(!wheel.contains(far));
+    assert!(wheel.is_empty());
+}